@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
-#[derive(Debug, Clone, PartialEq, Eq, Display, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Action {
     Tick,
     Render,
@@ -12,4 +13,42 @@ pub enum Action {
     ClearScreen,
     Error(String),
     Help,
+    CopyConsole,
+    ExportConsole,
+    PreviousTab,
+    NextTab,
+    ToggleMouseCapture,
+    ToggleConsoleMouse,
+    RepeatCommand,
+    ToggleRenderStats,
+    ToggleInline,
+    ToggleCompact,
+    CycleLogLevel,
+    /// Logs the app state for bug reports. Only bound with the `debug-state` feature and left
+    /// out of the help.
+    DumpState,
+}
+
+impl Action {
+    /// What the action does, as listed in the help overlay.
+    pub fn description(&self) -> String {
+        match self {
+            Action::Quit => "Quit".to_string(),
+            Action::ClearScreen => "Clear the console".to_string(),
+            Action::Help => "Show or hide this help".to_string(),
+            Action::CopyConsole => "Copy the whole console".to_string(),
+            Action::ExportConsole => "Save the console to a file".to_string(),
+            Action::PreviousTab => "Previous log tab".to_string(),
+            Action::NextTab => "Next log tab".to_string(),
+            Action::ToggleMouseCapture => "Toggle mouse capture".to_string(),
+            Action::ToggleConsoleMouse => "Capture the mouse only in the console".to_string(),
+            Action::ToggleRenderStats => "Toggle render stats".to_string(),
+            Action::ToggleInline => "Switch between full screen and inline".to_string(),
+            Action::ToggleCompact => "Toggle compact layout".to_string(),
+            Action::CycleLogLevel => "Cycle the log level".to_string(),
+            Action::RepeatCommand => "Repeat the last : command".to_string(),
+            Action::DumpState => "Log the app state".to_string(),
+            action => action.to_string(),
+        }
+    }
 }
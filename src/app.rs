@@ -1,36 +1,186 @@
+mod ui;
+
+use crate::action::Action;
+use crate::clipboard::{ClipboardWorker, Selection};
+use crate::command::Command;
+use crate::config::{self, BellStyle, Config, ConsoleMode, EmptyEnter, LogTab, SubmitBehavior};
+use crate::console::{format_time, ConsoleLine, LineLevel};
+use crate::errors::AppError;
+use crate::history::History;
+use crate::keybindings::{KeyBinding, KeyBindings};
+use crate::logging;
+use crate::panel::{default_panels, Panel};
+use crate::sink::MessageSink;
+use crate::theme::Theme;
 use crate::tui::*;
+use chrono::Local;
 use color_eyre::Result;
-use crossterm::event::{Event, KeyCode, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use log::{debug, error, info, trace, warn, LevelFilter};
 use ratatui::prelude::*;
-use ratatui::widgets::canvas::Rectangle;
-use ratatui::widgets::{Block, Borders, Gauge, Paragraph, Tabs, Wrap};
+use regex::Regex;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{Display, Formatter};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::thread;
-use std::time::Duration;
-use tui_input::backend::crossterm::EventHandler;
-use tui_input::Input;
+use std::time::{Duration, Instant};
+use tui_input::backend::crossterm::to_input_request;
+use tui_input::{Input, InputRequest};
 use tui_logger::*;
+use unicode_width::UnicodeWidthChar;
 
-pub(crate) struct App {
+pub struct App {
+    config: Config,
+    keybindings: KeyBindings,
+    theme: Theme,
     input: Input,
     mode: AppMode,
     states: Vec<TuiWidgetState>,
     selected_tab: usize,
-    progress_counter: Option<u16>,
+    /// Most verbose level captured and shown in tabs without a level of their own.
+    log_level: LevelFilter,
+    /// Last reported percent of each task with a gauge.
+    progress: HashMap<TaskId, u16>,
+    /// Gauge positions in percent, eased toward `progress` on each tick.
+    displayed_progress: HashMap<TaskId, f64>,
+    /// Width of the left column as a percentage of the screen.
+    left_column_percent: u16,
+    progress_paused: bool,
+    progress_control: Option<mpsc::Sender<ProgressControl>>,
     input_rect: Rect,
     console_rect: Rect,
+    /// Track of the console scrollbar; empty while all messages fit.
+    scrollbar_rect: Rect,
+    status_rect: Rect,
     focus_mode: FocusMode,
+    /// Focusable panel under the mouse pointer, if any.
+    hovered: Option<FocusMode>,
     scroll: usize,
-    messages: Vec<String>,
+    console_scroll: usize,
+    /// Keep the console scrolled to the newest message as messages arrive.
+    follow: bool,
+    /// The console pane not being scrolled while the console is split in two. The active
+    /// pane lives in `console_rect`, `console_scroll` and `follow`.
+    split: Option<ConsolePane>,
+    line_numbers: bool,
+    /// Drop panel borders and titles to fit small terminals.
+    compact: bool,
+    /// Console lines, oldest first, capped at `config.max_messages`.
+    messages: VecDeque<ConsoleLine>,
+    history: History,
+    /// Finished lines of a multiline message, above the line being edited in `input`.
+    input_lines: Vec<String>,
+    multiline: bool,
+    /// Message moved over with `j`/`k` and copied whole with `y`, independent of the selection.
+    console_cursor: Option<usize>,
+    /// (line, column) moved with the arrow keys in visual mode (`v`), where it is the end of
+    /// the selection. `None` outside visual mode.
+    visual_cursor: Option<(usize, usize)>,
     selection_start: Option<(usize, usize)>, // (line, column)
     selection_end: Option<(usize, usize)>,   // (line, column)
     dragging: bool,
+    /// Time and screen cell of the last console click, and how many quick clicks led up to
+    /// it: one selects by character, two by word, three the whole line.
+    last_click: Option<(Instant, (u16, u16), u8)>,
+    /// Mouse position of a selection drag above or below the console, which keeps scrolling
+    /// it a line per tick.
+    autoscroll: Option<(u16, u16)>,
+    dragging_scrollbar: bool,
+    mouse_captured: bool,
+    /// Keep mouse capture to the console, releasing it when the mouse moves elsewhere.
+    mouse_console_only: bool,
+    /// Capture was released outside the console and comes back with the next key press.
+    mouse_released: bool,
+    flash: bool,
+    /// The `:` command line, while it is open.
+    command: Option<Input>,
+    /// The last command that ran successfully, repeated with `.` in the console.
+    last_command: Option<Command>,
+    search: Option<ReverseSearch>,
+    confirm: Option<Confirm>,
+    context_menu: Option<ContextMenu>,
+    /// Where the context menu was last drawn, for mouse hit-testing.
+    menu_rect: Rect,
+    status_message: Option<String>,
+    clipboard: Option<ClipboardWorker>,
+    /// A selection was sent to the clipboard and is cleared once the copy succeeds.
+    clear_selection_on_copy: bool,
+    /// Short-lived feedback shown in the footer until the given time.
+    toast: Option<(String, Instant, Style)>,
+    /// Number of tasks between [`AppEvent::TaskStarted`] and [`AppEvent::TaskFinished`].
+    active_tasks: usize,
+    /// Input sources started with [`spawn_source`] that have not sent
+    /// [`AppEvent::SourceEnded`] yet. Background tasks and workers hold the event channel open
+    /// for as long as the app runs, so a closed channel alone can't tell when input stops.
+    live_sources: usize,
+    /// Whether `start` spawns the tasks besides the event source, see
+    /// [`AppBuilder::background_tasks`].
+    background_tasks: bool,
+    history_path: Option<PathBuf>,
+    spinner_frame: usize,
+    /// Time of the last terminal event, for `config.idle_timeout_secs`.
+    last_activity: Instant,
+    /// Set when state changed since the last draw; idle ticks skip drawing otherwise.
+    dirty: bool,
+    /// Set by F9 for `run` to hand the terminal back to `start` for switching.
+    toggle_inline: bool,
+    /// Set by Ctrl+X Ctrl+E for `run` to hand the terminal back to `start`, which opens the
+    /// input in an external editor.
+    edit_externally: bool,
+    /// Ctrl+X was pressed in the input and the next key may complete a chord.
+    ctrl_x_pending: bool,
+    render_stats: Option<RenderStats>,
+    sinks: Vec<Box<dyn MessageSink>>,
+    /// The event channel, created up front so that [`RedrawHandle`]s can be handed out before
+    /// `start` takes it.
+    events: Option<(mpsc::Sender<AppEvent>, mpsc::Receiver<AppEvent>)>,
+    /// Panels at the top of the left column.
+    panels: Vec<Box<dyn Panel>>,
+    /// Scroll offset of the key binding help overlay while it is open.
+    help_scroll: Option<u16>,
+    /// Checks each submitted line; an `Err` keeps the input and shows the reason.
+    validator: Validator,
+    /// Compiled `config.highlights`, in order.
+    highlights: Vec<(Regex, Style)>,
+    /// The last submission was rejected, so the input border is drawn in the error color.
+    input_invalid: bool,
+    /// The whole input is selected after a submit, so typing replaces it.
+    input_selected: bool,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-enum FocusMode {
+/// Frame timings shown by the debug overlay. Only collected while the overlay is on.
+#[derive(Debug, Default)]
+struct RenderStats {
+    /// Start times of the frames drawn during the last second.
+    frames: VecDeque<Instant>,
+    last_render: Duration,
+}
+
+impl RenderStats {
+    fn record(&mut self, started: Instant) {
+        let now = Instant::now();
+        self.last_render = now - started;
+        self.frames.push_back(started);
+        while self
+            .frames
+            .front()
+            .is_some_and(|frame| now - *frame > Duration::from_secs(1))
+        {
+            self.frames.pop_front();
+        }
+    }
+}
+
+/// The panel that keys go to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusMode {
     #[default]
     Input,
     Console,
@@ -52,325 +202,4192 @@ enum AppMode {
     Quit,
 }
 
+/// Actions offered by the console's right-click menu, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuItem {
+    Copy,
+    SelectAll,
+    Clear,
+    Close,
+}
+
+impl MenuItem {
+    const ALL: [MenuItem; 4] = [
+        MenuItem::Copy,
+        MenuItem::SelectAll,
+        MenuItem::Clear,
+        MenuItem::Close,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            MenuItem::Copy => "Copy",
+            MenuItem::SelectAll => "Select All",
+            MenuItem::Clear => "Clear",
+            MenuItem::Close => "Close",
+        }
+    }
+}
+
+/// The console's right-click menu, opened at the clicked cell.
+#[derive(Debug)]
+struct ContextMenu {
+    column: u16,
+    row: u16,
+    selected: usize,
+}
+
+/// A yes/no question shown in a popup that captures all keys until answered.
+#[derive(Debug)]
+struct Confirm {
+    prompt: String,
+    action: ConfirmAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfirmAction {
+    Quit,
+    Clear,
+    RepeatCommand,
+}
+
+/// State of an incremental `Ctrl+R` search through the input history.
+#[derive(Debug, Default)]
+struct ReverseSearch {
+    query: String,
+    /// History index of the entry shown in the input line.
+    matched: Option<usize>,
+    /// Set when the last query change or `Ctrl+R` found nothing.
+    failing: bool,
+    /// Input line to restore if the search is cancelled.
+    saved: String,
+}
+
+/// Scroll state of one half of the split console.
+#[derive(Debug, Clone, Copy, Default)]
+struct ConsolePane {
+    rect: Rect,
+    scroll: usize,
+    follow: bool,
+    /// Whether this is the upper half.
+    top: bool,
+}
+
+/// Requests sent from the UI to the progress task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProgressControl {
+    TogglePause,
+    Restart,
+}
+
+/// Names a background task in progress and spinner events.
+pub type TaskId = &'static str;
+
+/// Task driven by [`ProgressControl`] requests.
+const PROGRESS_TASK: TaskId = "progress";
+
 #[derive(Debug)]
 pub enum AppEvent {
     UiEvent(Event),
-    CounterChanged(Option<u16>),
+    /// A task's progress in percent; `None` once it is done and its gauge should go.
+    CounterChanged(TaskId, Option<u16>),
+    /// A background task failed with the given message.
+    TaskError(String),
+    /// Outcome of a clipboard write: the number of characters copied or the failure reason.
+    CopyResult(Result<usize, String>),
+    /// A line read from stdin when it is piped, e.g. `echo foo | ratatui_demo`.
+    StdinLine(String),
+    /// A line for the console from a task. With `replace_last` it rewrites the newest line
+    /// instead when [`ConsoleMode::Editable`] is configured, like output ending in `\r`.
+    ConsoleUpdate {
+        replace_last: bool,
+        text: String,
+    },
+    /// A log record mirrored into the console, see `console_log_level`.
+    LogRecord(ConsoleLine),
+    /// A background task began work that the status bar spinner should show.
+    TaskStarted(TaskId),
+    /// A task that sent [`AppEvent::TaskStarted`] went idle.
+    TaskFinished(TaskId),
+    /// An input source, such as the terminal or piped stdin, stopped for good, e.g. at EOF or
+    /// after a read error.
+    SourceEnded(&'static str),
+    /// Quit through the normal shutdown path, e.g. on SIGTERM.
+    Quit,
+    /// Periodic wake-up for time-based state; draws only if something changed.
+    Tick,
+    /// Draw a frame even though no app state changed, e.g. after an external update.
+    Redraw,
 }
 
-impl App {
-    pub fn new() -> App {
-        let states = vec![
-            TuiWidgetState::new().set_default_display_level(LevelFilter::Info),
-            TuiWidgetState::new().set_default_display_level(LevelFilter::Info),
-            TuiWidgetState::new().set_default_display_level(LevelFilter::Info),
-            TuiWidgetState::new().set_default_display_level(LevelFilter::Info),
-        ];
-
-        App {
+/// Asks a running [`App`] to draw a frame, from any thread.
+#[derive(Debug, Clone)]
+pub struct RedrawHandle {
+    tx: mpsc::Sender<AppEvent>,
+}
+
+impl RedrawHandle {
+    /// Fails once the app has shut down.
+    pub fn request(&self) -> Result<(), AppError> {
+        self.tx
+            .send(AppEvent::Redraw)
+            .map_err(|_| AppError::TaskChannelClosed("app"))
+    }
+}
+
+/// Why [`App::start`] returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownReason {
+    /// The user or a signal asked to quit.
+    Quit,
+    /// The terminal input and any piped stdin ended, so no input could ever reach the app again.
+    EventSourcesEnded,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Events of which only the newest of each kind matters, because each one replaces the
+/// state set by the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StateUpdate {
+    Progress(TaskId),
+    Tick,
+    Resize,
+}
+
+impl AppEvent {
+    fn state_update(&self) -> Option<StateUpdate> {
+        match self {
+            AppEvent::CounterChanged(task, _) => Some(StateUpdate::Progress(task)),
+            AppEvent::Tick => Some(StateUpdate::Tick),
+            AppEvent::UiEvent(Event::Resize(..)) => Some(StateUpdate::Resize),
+            _ => None,
+        }
+    }
+}
+
+/// Configures an [`App`] before it starts. Starts from the default [`Config`], or from a
+/// loaded one with [`AppBuilder::with_config`].
+pub struct AppBuilder {
+    config: Config,
+    messages: Vec<ConsoleLine>,
+    background_tasks: bool,
+    history_path: Option<PathBuf>,
+}
+
+impl Default for AppBuilder {
+    fn default() -> AppBuilder {
+        AppBuilder::with_config(Config::default())
+    }
+}
+
+impl AppBuilder {
+    pub fn with_config(config: Config) -> AppBuilder {
+        AppBuilder {
+            config,
+            messages: vec![],
+            background_tasks: true,
+            history_path: Some(config::get_history_path()),
+        }
+    }
+
+    /// Interval between ticks, which expire toasts and advance the spinner and gauge.
+    pub fn tick_rate(mut self, tick_rate: Duration) -> AppBuilder {
+        self.config.tick_rate_ms = tick_rate.as_millis().try_into().unwrap_or(u64::MAX);
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> AppBuilder {
+        self.config.theme = theme;
+        self
+    }
+
+    /// The panel focused on the first frame.
+    pub fn initial_focus(mut self, focus: FocusMode) -> AppBuilder {
+        self.config.initial_focus = focus;
+        self
+    }
+
+    /// Number of console lines kept before the oldest are dropped.
+    pub fn max_messages(mut self, max_messages: usize) -> AppBuilder {
+        self.config.max_messages = max_messages;
+        self
+    }
+
+    /// Keys for global actions. Actions left out keep their current key.
+    pub fn keybindings(
+        mut self,
+        keybindings: impl IntoIterator<Item = (Action, KeyBinding)>,
+    ) -> AppBuilder {
+        self.config.keybindings.extend(keybindings);
+        self
+    }
+
+    /// Lines already in the console when the app starts, oldest first.
+    pub fn initial_messages<L: Into<ConsoleLine>>(
+        mut self,
+        messages: impl IntoIterator<Item = L>,
+    ) -> AppBuilder {
+        self.messages.extend(messages.into_iter().map(Into::into));
+        self
+    }
+
+    /// Whether `start` runs the tick, progress, download and signal tasks and reads piped
+    /// stdin next to its event source. Without them the app only sees the source's events,
+    /// so a [`ScriptedEvents`] run plays out the same every time and ends with the script.
+    pub fn background_tasks(mut self, enabled: bool) -> AppBuilder {
+        self.background_tasks = enabled;
+        self
+    }
+
+    /// File the input history is loaded from on start and saved to on shutdown,
+    /// `history.txt` in the data directory by default. `None` keeps it in memory.
+    pub fn history_path(mut self, path: Option<PathBuf>) -> AppBuilder {
+        self.history_path = path;
+        self
+    }
+
+    pub fn build(self) -> App {
+        let config = self.config;
+        let log_level = LevelFilter::Info;
+        let states = log_tab_states(&config.log_tabs, log_level);
+
+        let mut app = App {
+            theme: if config.color.enabled() {
+                config.theme.clone()
+            } else {
+                Theme::monochrome()
+            },
+            keybindings: KeyBindings::with_overrides(&config.keybindings),
             input: Input::default(),
             mode: AppMode::Run,
             states,
             selected_tab: 0,
-            progress_counter: None,
+            log_level,
+            progress: HashMap::new(),
+            displayed_progress: HashMap::new(),
+            left_column_percent: config
+                .left_column_percent
+                .clamp(MIN_LEFT_COLUMN_PERCENT, MAX_LEFT_COLUMN_PERCENT),
+            progress_paused: false,
+            progress_control: None,
             input_rect: Default::default(),
             console_rect: Default::default(),
-            focus_mode: Default::default(),
+            scrollbar_rect: Default::default(),
+            status_rect: Default::default(),
+            focus_mode: config.initial_focus,
+            hovered: None,
             scroll: 0,
-            messages: vec![],
+            console_scroll: 0,
+            follow: true,
+            split: None,
+            line_numbers: config.line_numbers,
+            compact: config.compact,
+            messages: VecDeque::new(),
+            history: History::new(config.history_size)
+                .with_control(config.history_ignore_dups, config.history_ignore_space),
+            input_lines: vec![],
+            multiline: config.multiline_input,
+            console_cursor: None,
+            visual_cursor: None,
             selection_start: None,
             selection_end: None,
             dragging: false,
+            last_click: None,
+            autoscroll: None,
+            dragging_scrollbar: false,
+            mouse_captured: true,
+            mouse_console_only: config.mouse_console_only,
+            mouse_released: false,
+            flash: false,
+            command: None,
+            last_command: None,
+            search: None,
+            confirm: None,
+            context_menu: None,
+            menu_rect: Rect::default(),
+            status_message: None,
+            clipboard: None,
+            clear_selection_on_copy: false,
+            toast: None,
+            active_tasks: 0,
+            live_sources: 0,
+            background_tasks: self.background_tasks,
+            history_path: self.history_path,
+            spinner_frame: 0,
+            last_activity: Instant::now(),
+            dirty: true,
+            toggle_inline: false,
+            edit_externally: false,
+            ctrl_x_pending: false,
+            render_stats: None,
+            sinks: vec![],
+            events: Some(mpsc::channel()),
+            panels: default_panels(),
+            help_scroll: None,
+            validator: config_validator(&config),
+            highlights: vec![],
+            input_invalid: false,
+            input_selected: false,
+            config,
+        };
+        app.compile_highlights();
+        for message in self.messages {
+            app.push_message(message);
+        }
+        app
+    }
+}
+
+impl App {
+    pub fn new() -> App {
+        AppBuilder::default().build()
+    }
+
+    pub fn with_config(config: Config) -> App {
+        AppBuilder::with_config(config).build()
+    }
+
+    /// Compiles `config.highlights`, skipping and warning about invalid patterns.
+    fn compile_highlights(&mut self) {
+        let mut invalid = vec![];
+        self.highlights = self
+            .config
+            .highlights
+            .iter()
+            .filter_map(|rule| match Regex::new(&rule.pattern) {
+                Ok(regex) => Some((regex, rule.style())),
+                Err(err) => {
+                    warn!(target: "App", "Ignoring highlight pattern {:?}: {}", rule.pattern, err);
+                    invalid.push(rule.pattern.clone());
+                    None
+                }
+            })
+            .collect();
+        if !invalid.is_empty() {
+            self.show_error(format!(
+                "Invalid highlight patterns: {}",
+                invalid.join(", ")
+            ));
         }
     }
 
-    pub fn start(mut self, terminal: &mut Terminal<impl Backend>) -> Result<()> {
+    /// Style added to a console line by the highlight rules it matches. Monochrome themes
+    /// only get the bold.
+    fn highlight_style(&self, text: &str) -> Style {
+        self.highlights
+            .iter()
+            .filter(|(regex, _)| regex.is_match(text))
+            .fold(Style::default(), |style, (_, rule)| {
+                if self.theme.monochrome {
+                    style.add_modifier(rule.add_modifier)
+                } else {
+                    style.patch(*rule)
+                }
+            })
+    }
+
+    /// Tees every submitted input line out to `sink`.
+    pub fn add_sink(&mut self, sink: Box<dyn MessageSink>) {
+        self.sinks.push(sink);
+    }
+
+    /// Runs the `:` commands in `path`, one per line, as if typed on the command line. Blank
+    /// lines and lines starting with `#` are skipped. Stops at the first line that does not
+    /// parse, before running anything after it. `quit` is refused too, since the app hasn't
+    /// started yet and would only quit at its first event. `clear` clears without asking,
+    /// as the script already decided to.
+    pub fn run_script(&mut self, path: &Path) -> Result<(), AppError> {
+        let script = std::fs::read_to_string(path)?;
+        for (index, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| AppError::Script {
+                line: index + 1,
+                message,
+            };
+            let command = line
+                .parse::<Command>()
+                .map_err(|err| error(err.to_string()))?;
+            match command {
+                Command::Quit => return Err(error("quit can't run from a script".to_string())),
+                Command::Clear => self.clear_console(),
+                command => self.execute(command),
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the checks run on submitted lines, which default to the `input_*` settings
+    /// in `config.toml`.
+    pub fn set_validator(&mut self, validator: impl Fn(&str) -> Result<(), String> + 'static) {
+        self.validator = Box::new(validator);
+    }
+
+    /// Replaces the panels at the top of the left column, [`logo`](crate::panel::logo) and
+    /// [`session_info`](crate::panel::session_info) by default.
+    pub fn set_panels(&mut self, panels: Vec<Box<dyn Panel>>) {
+        self.panels = panels;
+    }
+
+    /// A handle for requesting a redraw from other threads while the app runs.
+    pub fn redraw_handle(&self) -> RedrawHandle {
+        let (tx, _) = self
+            .events
+            .as_ref()
+            .expect("only `start` takes the event channel");
+        RedrawHandle { tx: tx.clone() }
+    }
+
+    /// Runs the app on `terminal` with input from `source`, usually [`CrosstermEvents`], until
+    /// it quits or runs out of input.
+    pub fn start(
+        mut self,
+        terminal: &mut impl AppTerminal,
+        source: impl EventSource,
+    ) -> Result<ShutdownReason> {
         // Use an mpsc::channel to combine stdin events with app events
-        let (tx, rx) = mpsc::channel();
-        let event_tx = tx.clone();
-        let progress_tx = tx.clone();
+        let (tx, rx) = self.events.take().unwrap_or_else(mpsc::channel);
+        self.clipboard = Some(ClipboardWorker::spawn(tx.clone()));
+
+        self.spawn_source("input", tx.clone(), move |tx| input_thread(source, tx));
+        if let Some(level) = self.config.console_log_level {
+            logging::mirror_to_console(tx.clone(), level);
+        }
+        if self.background_tasks {
+            self.spawn_background_tasks(tx);
+        }
+
+        if let Some(path) = &self.history_path {
+            self.history = History::load(path, self.config.history_size).with_control(
+                self.config.history_ignore_dups,
+                self.config.history_ignore_space,
+            );
+        }
+        let result = loop {
+            match self.run(terminal.terminal(), &rx) {
+                Ok(None) if std::mem::take(&mut self.edit_externally) => {
+                    if let Err(err) = self.edit_input_externally(terminal) {
+                        break Err(err.into());
+                    }
+                    if let Err(err) = self.draw(terminal.terminal()) {
+                        break Err(err);
+                    }
+                }
+                Ok(None) => {
+                    self.toggle_inline = false;
+                    if let Err(err) = terminal.toggle_inline(self.config.inline_height) {
+                        error!(target: "App", "Failed to switch viewport: {}", err);
+                        self.show_error(format!("Failed to switch viewport: {err}"));
+                    }
+                    if let Err(err) = self.draw(terminal.terminal()) {
+                        break Err(err);
+                    }
+                }
+                Ok(Some(reason)) => break Ok(reason),
+                Err(err) => break Err(err),
+            }
+        };
+        if !self.mouse_captured {
+            // leave the terminal in the state `restore_terminal` expects to undo
+            self.toggle_mouse_capture();
+        }
+        if self.config.console_log_level.is_some() {
+            logging::stop_mirroring();
+        }
+        if let Some(path) = &self.history_path {
+            if let Err(err) = self.history.save(path) {
+                error!(target: "App", "Failed to save input history: {}", err);
+            }
+        }
+        result
+    }
+
+    /// Starts the signal handler, the piped stdin reader and the tasks that tick, report
+    /// progress and simulate work.
+    fn spawn_background_tasks(&mut self, tx: mpsc::Sender<AppEvent>) {
+        #[cfg(unix)]
+        spawn_task("signals", tx.clone(), signal_thread);
+        // crossterm reads keys from the controlling terminal when stdin is a pipe
+        if !io::stdin().is_terminal() {
+            self.spawn_source("stdin", tx.clone(), stdin_task);
+        }
+        let (control_tx, control_rx) = mpsc::channel();
+        self.progress_control = Some(control_tx);
+        spawn_task(PROGRESS_TASK, tx.clone(), move |tx| {
+            progress_task(tx, control_rx)
+        });
+        spawn_task("download", tx.clone(), download_task);
+        thread::spawn(background_task);
+        let tick_rate = Duration::from_millis(self.config.tick_rate_ms.max(1));
+        spawn_task("tick", tx, move |tx| tick_task(tx, tick_rate));
+    }
+
+    /// Suspends the terminal to edit the input in `$VISUAL` or `$EDITOR`, then puts the
+    /// result in the input. A failing editor, or a terminal that can't be suspended, only
+    /// shows an error; failing to take the terminal back is fatal.
+    fn edit_input_externally(&mut self, terminal: &mut impl AppTerminal) -> Result<(), AppError> {
+        let text = self
+            .input_lines
+            .iter()
+            .map(String::as_str)
+            .chain([self.input.value()])
+            .collect::<Vec<_>>()
+            .join("\n");
+        match terminal.suspend(self.config.inline_height, || edit_in_editor(&text)) {
+            Ok(Ok(edited)) => self.set_input_text(&edited),
+            Ok(Err(err)) => {
+                error!(target: "App", "External editor failed: {}", err);
+                self.show_error(format!("Editor failed: {err}"));
+            }
+            Err(err @ AppError::Unsupported(_)) => {
+                self.show_error(format!("Editor failed: {err}"));
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        }
+        if !self.mouse_captured {
+            // the terminal comes back with capture on
+            set_mouse_capture(false)?;
+        }
+        self.mouse_released = false;
+        terminal.terminal().clear()?;
+        Ok(())
+    }
 
-        thread::spawn(move || input_thread(event_tx));
-        thread::spawn(move || progress_task(progress_tx).unwrap());
-        thread::spawn(move || background_task());
+    /// Replaces the input with `text`, one input line per text line in multiline mode and
+    /// joined with spaces otherwise.
+    fn set_input_text(&mut self, text: &str) {
+        let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+        let last = lines.pop().unwrap_or_default();
+        self.clear_input();
+        if self.multiline {
+            self.input_lines = lines;
+            self.input = Input::new(last);
+        } else {
+            lines.push(last);
+            self.input = Input::new(lines.join(" "));
+        }
+    }
 
-        self.run(terminal, rx)
+    /// Inserts a paste at the cursor in multiline mode, breaking the input into lines where
+    /// the pasted text does.
+    fn paste_lines(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n");
+        let mut lines = text.split(['\n', '\r']);
+        let first = lines.next().unwrap_or_default();
+        let rest: Vec<&str> = lines.collect();
+        let Some((last, middle)) = rest.split_last() else {
+            paste(&mut self.input, first);
+            return;
+        };
+        let value = self.input.value();
+        let split = value
+            .char_indices()
+            .nth(self.input.cursor())
+            .map_or(value.len(), |(index, _)| index);
+        let (before, after) = value.split_at(split);
+        let first = format!("{before}{first}");
+        let current = format!("{last}{after}");
+        self.input_lines.push(first);
+        self.input_lines
+            .extend(middle.iter().map(|line| line.to_string()));
+        self.input = Input::new(current).with_cursor(last.chars().count());
     }
 
-    /// Main application loop
+    /// Main application loop. Returns `None` when the terminal needs switching between the
+    /// alternate screen and inline or handing to an editor, which only the caller owning it
+    /// can do.
     fn run(
         &mut self,
         terminal: &mut Terminal<impl Backend>,
-        rx: mpsc::Receiver<AppEvent>,
-    ) -> Result<()> {
-        for event in rx {
-            match event {
-                AppEvent::UiEvent(event) => self.handle_ui_event(event),
-                AppEvent::CounterChanged(value) => self.update_progress_bar(event, value),
-            }
-            if self.mode == AppMode::Quit {
-                break;
+        rx: &mpsc::Receiver<AppEvent>,
+    ) -> Result<Option<ShutdownReason>> {
+        while let Ok(event) = rx.recv() {
+            // handle everything that queued up during the last draw before drawing again
+            let mut batch: Vec<AppEvent> = std::iter::once(event).chain(rx.try_iter()).collect();
+            settle_resizes(&mut batch, rx);
+            for event in coalesce(batch) {
+                let source_ended = matches!(event, AppEvent::SourceEnded(_));
+                self.handle_event(event);
+                if self.mode == AppMode::Quit {
+                    return Ok(Some(ShutdownReason::Quit));
+                }
+                if source_ended && self.live_sources == 0 {
+                    return self.sources_ended(terminal);
+                }
+            }
+            if self.toggle_inline || self.edit_externally {
+                return Ok(None);
+            }
+            if self.needs_draw() {
+                self.draw(terminal)?;
             }
-            self.draw(terminal)?;
         }
-        Ok(())
+        self.sources_ended(terminal)
+    }
+
+    /// Shows that nothing can reach the app any more, since the UI can't take input to quit.
+    fn sources_ended(
+        &mut self,
+        terminal: &mut Terminal<impl Backend>,
+    ) -> Result<Option<ShutdownReason>> {
+        error!(target: "App", "All event sources ended");
+        self.status_message = Some("all event sources ended".to_string());
+        self.draw(terminal)?;
+        Ok(Some(ShutdownReason::EventSourcesEnded))
+    }
+
+    /// Starts an input source with [`spawn_task`] and counts it as live until it ends.
+    fn spawn_source<F>(&mut self, name: &'static str, tx: mpsc::Sender<AppEvent>, source: F)
+    where
+        F: FnOnce(mpsc::Sender<AppEvent>) -> anyhow::Result<()> + Send + 'static,
+    {
+        self.live_sources += 1;
+        spawn_task(name, tx.clone(), move |source_tx| {
+            // report the error before the end, which may shut the app down
+            if let Err(err) = source(source_tx) {
+                let _ = tx.send(AppEvent::TaskError(format!("{name}: {err}")));
+            }
+            tx.send(AppEvent::SourceEnded(name))?;
+            Ok(())
+        });
+    }
+
+    /// Applies one event to the app state without drawing. Every event except an idle tick
+    /// marks the app dirty so the next draw picks up the change.
+    fn handle_event(&mut self, event: AppEvent) {
+        if !matches!(event, AppEvent::Tick) {
+            self.dirty = true;
+        }
+        match event {
+            AppEvent::Tick => self.tick(),
+            // the dirty flag set above is all a redraw needs
+            AppEvent::Redraw => {}
+            AppEvent::Quit => {
+                info!(target: "App", "Quit requested");
+                self.mode = AppMode::Quit;
+            }
+            AppEvent::StdinLine(line) => self.push_message(line.into()),
+            // not logged: that would be mirrored straight back
+            AppEvent::LogRecord(line) => self.push_message(line),
+            AppEvent::ConsoleUpdate {
+                replace_last: true,
+                text,
+            } => self.update_last_line(text),
+            AppEvent::ConsoleUpdate { text, .. } => self.push_message(text.into()),
+            AppEvent::TaskStarted(name) => {
+                debug!(target: "App", "Task {} started", name);
+                self.active_tasks += 1;
+            }
+            AppEvent::TaskFinished(name) => {
+                debug!(target: "App", "Task {} finished", name);
+                self.active_tasks = self.active_tasks.saturating_sub(1);
+            }
+            AppEvent::SourceEnded(name) => {
+                info!(target: "App", "Event source {} ended", name);
+                self.live_sources = self.live_sources.saturating_sub(1);
+            }
+            AppEvent::UiEvent(event) => {
+                self.last_activity = Instant::now();
+                self.handle_ui_event(event)
+            }
+            AppEvent::CounterChanged(task, value) => self.update_progress_bar(task, value),
+            AppEvent::TaskError(message) => {
+                error!(target: "App", "Background task failed: {}", message);
+                self.show_error(format!("Task failed: {message}"));
+                self.push_message(ConsoleLine::error(format!("task failed: {message}")));
+            }
+            AppEvent::CopyResult(Ok(chars)) => {
+                if std::mem::take(&mut self.clear_selection_on_copy) {
+                    self.selection_start = None;
+                    self.selection_end = None;
+                }
+                self.show_toast(format!("Copied {chars} chars"))
+            }
+            AppEvent::CopyResult(Err(err)) => {
+                self.clear_selection_on_copy = false;
+                error!(target: "App", "Failed to copy selection: {}", err);
+                self.show_error(format!("Copy failed: {err}"));
+            }
+        }
+    }
+
+    fn needs_draw(&self) -> bool {
+        self.dirty || self.config.redraw_when_idle
+    }
+
+    /// Switches focus, always marking the app dirty so the border colors swap on the next
+    /// draw even if the event causing it is otherwise treated as idle.
+    fn set_focus(&mut self, focus: FocusMode) {
+        if self.focus_mode != focus {
+            self.focus_mode = focus;
+            self.dirty = true;
+        }
+    }
+
+    /// Marks the app dirty only for state that changes with time alone.
+    fn tick(&mut self) {
+        if self
+            .toast
+            .as_ref()
+            .is_some_and(|(_, expires, _)| *expires <= Instant::now())
+        {
+            self.toast = None;
+            self.dirty = true;
+        }
+        if self.render_stats.is_some() {
+            // keep the frame rate readout live
+            self.dirty = true;
+        }
+        if self.active_tasks > 0 {
+            self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
+            self.dirty = true;
+        }
+        for (task, &progress) in &self.progress {
+            let target = f64::from(progress.min(100));
+            let displayed = self.displayed_progress.entry(task).or_insert(0.0);
+            if *displayed != target {
+                let step = (target - *displayed) * self.config.progress_smoothing.min(1.0);
+                *displayed = if step.abs() < 0.1 {
+                    target
+                } else {
+                    *displayed + step
+                };
+                self.dirty = true;
+            }
+        }
+        if let Some(remaining) = self.idle_remaining() {
+            if remaining.is_zero() {
+                info!(target: "App", "Idle timeout, running {}", self.config.idle_action);
+                self.last_activity = Instant::now();
+                self.perform(self.config.idle_action.clone());
+            }
+            // keep the countdown ticking
+            self.dirty |= remaining <= IDLE_COUNTDOWN;
+        }
+        if let Some((row, col)) = self.autoscroll {
+            if row < self.console_inner().top() {
+                self.scroll_console_up(1);
+            } else {
+                self.scroll_console_down(1);
+            }
+            self.extend_selection_to(row, col);
+            self.dirty = true;
+        }
+    }
+
+    /// Time left until the idle timeout, if one is configured.
+    fn idle_remaining(&self) -> Option<Duration> {
+        let timeout = Duration::from_secs(self.config.idle_timeout_secs?);
+        Some(timeout.saturating_sub(self.last_activity.elapsed()))
+    }
+
+    /// Status bar warning during the last seconds before the idle timeout.
+    fn idle_countdown(&self) -> Option<String> {
+        let remaining = self
+            .idle_remaining()
+            .filter(|left| *left <= IDLE_COUNTDOWN)?;
+        Some(format!(
+            " {} in {}s ",
+            self.config.idle_action.description(),
+            remaining.as_secs_f64().ceil()
+        ))
     }
 
-    fn update_progress_bar(&mut self, event: AppEvent, value: Option<u16>) {
-        // trace!(target: "App", "Updating progress bar {:?}",event);
-        self.progress_counter = value;
-        if value.is_none() {
-            info!(target: "App", "Background task finished");
+    fn update_progress_bar(&mut self, task: TaskId, value: Option<u16>) {
+        let Some(progress) = value else {
+            self.progress.remove(task);
+            self.displayed_progress.remove(task);
+            info!(target: "App", "Background task {} finished", task);
+            return;
+        };
+        self.progress.insert(task, progress);
+        let target = f64::from(progress.min(100));
+        let displayed = self.displayed_progress.entry(task).or_insert(0.0);
+        // only forward motion is eased; a restart jumps straight back
+        if self.config.progress_smoothing <= 0.0 || target < *displayed {
+            *displayed = target;
         }
     }
 
+    fn control_progress(&mut self, control: ProgressControl) {
+        match control {
+            ProgressControl::TogglePause => self.progress_paused = !self.progress_paused,
+            ProgressControl::Restart => self.progress_paused = false,
+        }
+        if let Some(progress_control) = &self.progress_control {
+            let _ = progress_control.send(control);
+        }
+    }
+
+    /// Gauge ratio and label of each task with progress, ordered by task name, or a
+    /// single idle gauge when there are none.
+    fn progress_gauges(&self) -> Vec<(f64, String)> {
+        let mut tasks: Vec<_> = self.progress.iter().collect();
+        if tasks.is_empty() {
+            return vec![(0.0, "Idle".to_string())];
+        }
+        tasks.sort_unstable_by_key(|(task, _)| **task);
+        tasks
+            .into_iter()
+            .map(|(&task, progress)| {
+                let displayed = self.displayed_progress.get(task).copied().unwrap_or(0.0);
+                let label = if task == PROGRESS_TASK && self.progress_paused {
+                    format!("{task} paused {progress}%")
+                } else {
+                    format!("{task} {progress}%")
+                };
+                (displayed.clamp(0.0, 100.0) / 100.0, label)
+            })
+            .collect()
+    }
+
     fn handle_ui_event(&mut self, event: Event) {
         trace!(target: "App", "Handling UI event: {:?}",event);
 
+        if let Event::Key(_) | Event::FocusGained = event {
+            self.recapture_mouse();
+        }
+
         if let Event::Mouse(mouse_event) = event {
             let mouse_row = mouse_event.row;
             let mouse_col = mouse_event.column;
 
+            if self.mouse_console_only
+                && !self.dragging
+                && !self.dragging_scrollbar
+                && self.context_menu.is_none()
+                && self.help_scroll.is_none()
+                && !self.in_console(mouse_row, mouse_col)
+            {
+                self.release_mouse();
+                return;
+            }
+
+            if self.context_menu.is_some() {
+                self.handle_menu_mouse(mouse_event);
+                return;
+            }
+            if let Some(scroll) = self.help_scroll.as_mut() {
+                match mouse_event.kind {
+                    MouseEventKind::ScrollUp => *scroll = scroll.saturating_sub(1),
+                    MouseEventKind::ScrollDown => *scroll += 1,
+                    _ => {}
+                }
+                return;
+            }
+            if let MouseEventKind::Down(_) | MouseEventKind::ScrollUp | MouseEventKind::ScrollDown =
+                mouse_event.kind
+            {
+                if self
+                    .split
+                    .is_some_and(|pane| self.rect_contains(pane.rect, mouse_row, mouse_col))
+                {
+                    self.swap_console_panes();
+                }
+            }
             match mouse_event.kind {
+                MouseEventKind::Down(MouseButton::Right)
+                    if self.rect_contains(self.console_rect, mouse_row, mouse_col) =>
+                {
+                    self.set_focus(FocusMode::Console);
+                    self.context_menu = Some(ContextMenu {
+                        column: mouse_col,
+                        row: mouse_row,
+                        selected: 0,
+                    });
+                }
                 MouseEventKind::Down(MouseButton::Left) => {
-                    if self.rect_contains(self.input_rect, mouse_row, mouse_col) {
-                        self.focus_mode = FocusMode::Input;
+                    if self.rect_contains(self.scrollbar_rect, mouse_row, mouse_col) {
+                        self.dragging_scrollbar = true;
+                        self.scroll_console_to_track(mouse_row);
+                    } else if self.rect_contains(self.input_rect, mouse_row, mouse_col) {
+                        self.set_focus(FocusMode::Input);
                         self.dragging = false;
+                        self.move_input_cursor_to(mouse_row, mouse_col);
                     } else if self.rect_contains(self.console_rect, mouse_row, mouse_col) {
-                        self.focus_mode = FocusMode::Console;
-                        // Start selection
-                        let relative_row = mouse_row - self.console_rect.y;
-                        let relative_col = mouse_col - self.console_rect.x;
-                        self.selection_start = Some((relative_row as usize, relative_col as usize));
-                        self.selection_end = self.selection_start;
-                        self.dragging = true;
+                        self.set_focus(FocusMode::Console);
+                        // the mouse takes over from keyboard selection
+                        self.visual_cursor = None;
+                        let extend = mouse_event.modifiers.contains(KeyModifiers::SHIFT)
+                            && self.selection_start.is_some();
+                        if extend {
+                            // keep the anchor and move only the far end, as editors do
+                            self.last_click = None;
+                            self.extend_selection_to(mouse_row, mouse_col);
+                            self.dragging = true;
+                        } else {
+                            self.click_console(mouse_row, mouse_col);
+                        }
                     } else {
                         self.selection_start = None;
                         self.selection_end = None;
                         self.dragging = false;
                     }
                 }
-                MouseEventKind::Drag(MouseButton::Left) => {
-                    if self.dragging && self.focus_mode == FocusMode::Console {
-                        let relative_row = mouse_row - self.console_rect.y;
-                        let relative_col = mouse_col - self.console_rect.x;
-                        self.selection_end = Some((relative_row as usize, relative_col as usize));
-                    }
+                MouseEventKind::Drag(MouseButton::Left) if self.dragging_scrollbar => {
+                    self.scroll_console_to_track(mouse_row);
+                }
+                MouseEventKind::Drag(MouseButton::Left)
+                    if self.dragging && self.focus_mode == FocusMode::Console =>
+                {
+                    let inner = self.console_inner();
+                    self.autoscroll = (mouse_row < inner.top() || mouse_row >= inner.bottom())
+                        .then_some((mouse_row, mouse_col));
+                    self.extend_selection_to(mouse_row, mouse_col);
                 }
                 MouseEventKind::Up(MouseButton::Left) => {
+                    let dragged = self.dragging && self.selection_start != self.selection_end;
                     self.dragging = false;
+                    self.autoscroll = None;
+                    self.dragging_scrollbar = false;
+                    if dragged && self.config.copy_on_select {
+                        self.copy_selection(Selection::Primary);
+                    }
+                }
+                MouseEventKind::ScrollUp
+                    if self.rect_contains(self.console_rect, mouse_row, mouse_col) =>
+                {
+                    self.scroll_console_up(self.config.scroll_step);
                 }
+                MouseEventKind::ScrollDown
+                    if self.rect_contains(self.console_rect, mouse_row, mouse_col) =>
+                {
+                    self.scroll_console_down(self.config.scroll_step);
+                }
+                MouseEventKind::Moved => self.hovered = self.panel_at(mouse_row, mouse_col),
                 _ => {}
             }
         }
 
+        if let Event::Paste(text) = &event {
+            if let Some(command) = self.command.as_mut() {
+                paste(command, text);
+            } else if self.focus_mode == FocusMode::Input {
+                if std::mem::take(&mut self.input_selected) {
+                    self.clear_input();
+                }
+                if self.multiline {
+                    self.paste_lines(text);
+                } else {
+                    paste(&mut self.input, text);
+                }
+            } else {
+                self.unhandled_key();
+            }
+        }
+
         if let Event::Key(key) = event {
             debug!(target: "App", "Handling Key event: {:?}",event);
-            let code = key.code;
+            // only reported by Windows and by the Kitty protocol with event types enabled
+            if key.kind == KeyEventKind::Release {
+                return;
+            }
 
-            if self.focus_mode == FocusMode::Console {
-                match key.code {
-                    KeyCode::Esc => {
-                        self.selection_start = None;
-                        self.selection_end = None;
-                    }
-                    KeyCode::Tab => self.focus_mode = FocusMode::Input,
-                    _ => {}
+            let handled = if self.confirm.is_some() {
+                self.handle_confirm_key(key)
+            } else if self.help_scroll.is_some() {
+                self.handle_help_key(key)
+            } else if self.context_menu.is_some() {
+                self.handle_menu_key(key)
+            } else if self.command.is_some() {
+                self.handle_command_key(&event)
+            } else if self.handle_global_key(key) {
+                true
+            } else {
+                match self.focus_mode {
+                    FocusMode::Console => self.handle_console_key(key),
+                    FocusMode::Input => self.handle_input_key(&event),
                 }
+            };
+            if !handled {
+                self.unhandled_key();
             }
-            if self.focus_mode == FocusMode::Input {
-                match code.into() {
-                    KeyCode::Enter => {
-                        self.messages.push(self.input.value().into());
-                        self.input.reset();
-                        debug!("{:?}", self.messages);
-                    }
-                    KeyCode::Esc => self.mode = AppMode::Quit,
-                    _ => (),
+        }
+    }
+
+    /// Handles keys that work regardless of the focused panel.
+    fn handle_global_key(&mut self, key: KeyEvent) -> bool {
+        if key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL {
+            return self.interrupt();
+        }
+        match self.keybindings.action_for(&key).cloned() {
+            Some(action) => self.perform(action),
+            None => false,
+        }
+    }
+
+    /// Runs a bound action, returning false for actions that can't be bound to a key.
+    fn perform(&mut self, action: Action) -> bool {
+        match action {
+            Action::Quit => self.mode = AppMode::Quit,
+            Action::ClearScreen => self.request_clear(),
+            Action::CopyConsole => self.copy_console(),
+            Action::ExportConsole => self.export_console(),
+            Action::PreviousTab => self.previous_tab(),
+            Action::NextTab => self.next_tab(),
+            Action::ToggleMouseCapture => self.toggle_mouse_capture(),
+            Action::ToggleConsoleMouse => self.toggle_console_mouse(),
+            Action::RepeatCommand => self.repeat_command(),
+            Action::ToggleInline => self.toggle_inline = true,
+            Action::ToggleCompact => self.compact = !self.compact,
+            Action::CycleLogLevel => self.cycle_log_level(),
+            #[cfg(feature = "debug-state")]
+            Action::DumpState => self.dump_state(),
+            Action::Help => self.toggle_help(),
+            Action::ToggleRenderStats => {
+                self.render_stats = match self.render_stats {
+                    Some(_) => None,
+                    None => Some(RenderStats::default()),
                 }
-                self.input.handle_event(&event);
             }
+            _ => return false,
         }
+        true
     }
 
-    fn rect_contains(&self, rect: Rect, row: u16, col: u16) -> bool {
-        row >= rect.y && row < rect.y + rect.height && col >= rect.x && col < rect.x + rect.width
-    }
-    fn selected_state(&mut self) -> &mut TuiWidgetState {
-        &mut self.states[self.selected_tab]
+    /// `Ctrl+C` copies when the console is focused and has a selection. Otherwise, including
+    /// whenever the input is focused, it quits (asking first if `confirm_quit` is set), unless
+    /// `ctrl_c_quits` is turned off.
+    fn interrupt(&mut self) -> bool {
+        if self.focus_mode == FocusMode::Console && self.selection_range().is_some() {
+            self.copy_selection(Selection::Clipboard);
+            self.visual_cursor = None;
+        } else if self.config.ctrl_c_quits {
+            self.request_quit();
+        } else {
+            return false;
+        }
+        true
     }
 
-    // fn next_tab(&mut self) {
-    //     self.selected_tab = (self.selected_tab + 1) % self.tab_names.len();
-    // }
+    fn request_quit(&mut self) {
+        if self.config.confirm_quit {
+            self.confirm = Some(Confirm {
+                prompt: "Quit?".to_string(),
+                action: ConfirmAction::Quit,
+            });
+        } else {
+            self.mode = AppMode::Quit;
+        }
+    }
 
-    fn draw(&mut self, terminal: &mut Terminal<impl Backend>) -> Result<()> {
-        terminal.draw(|frame| {
-            let input_rect = self.input_rect.clone();
-            let scroll = self.scroll;
-            let input = self.input.clone();
-            let focus_mode = self.focus_mode;
-            frame.render_widget(self, frame.size());
-            if focus_mode == FocusMode::Input {
-                frame.set_cursor(
-                    // Put cursor past the end of the input text
-                    input_rect.x + (input.visual_cursor().max(scroll) - scroll) as u16 + 1,
-                    // Move one line down, from the border to the input line
-                    input_rect.y + 1,
-                )
-            }
-        })?;
+    fn request_clear(&mut self) {
+        if self.config.confirm_clear {
+            self.confirm = Some(Confirm {
+                prompt: "Clear console?".to_string(),
+                action: ConfirmAction::Clear,
+            });
+        } else {
+            self.clear_console();
+        }
+    }
 
-        Ok(())
+    /// Opens the key binding overlay at the top, or closes it.
+    fn toggle_help(&mut self) {
+        self.help_scroll = match self.help_scroll {
+            Some(_) => None,
+            None => Some(0),
+        };
     }
-}
 
-/// A simulated task that sends a counter value to the UI ranging from 0 to 100 every second.
-fn progress_task(tx: mpsc::Sender<AppEvent>) -> anyhow::Result<()> {
-    for progress in 0..100 {
-        // debug!(target:"progress-task", "Send progress to UI thread. Value: {:?}", progress);
-        tx.send(AppEvent::CounterChanged(Some(progress)))?;
+    /// Keys while the help overlay is open: scrolling, or `?`, `Esc` or the help binding to
+    /// close it.
+    fn handle_help_key(&mut self, key: KeyEvent) -> bool {
+        let Some(scroll) = self.help_scroll.as_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Up => *scroll = scroll.saturating_sub(1),
+            KeyCode::Down => *scroll += 1,
+            KeyCode::PageUp => *scroll = scroll.saturating_sub(10),
+            KeyCode::PageDown => *scroll += 10,
+            KeyCode::Home => *scroll = 0,
+            KeyCode::Esc | KeyCode::Char('?') => self.help_scroll = None,
+            _ if self.keybindings.action_for(&key) == Some(&Action::Help) => {
+                self.help_scroll = None
+            }
+            _ => return false,
+        }
+        true
+    }
 
-        // trace!(target:"progress-task", "Sleep one second");
-        thread::sleep(Duration::from_millis(1000));
+    /// Answers an open confirmation: `y`/`Enter` runs its action, `n`/`Esc` dismisses it.
+    /// A second `Ctrl+C` also confirms, so a double tap always gets out.
+    fn handle_confirm_key(&mut self, key: KeyEvent) -> bool {
+        let Some(confirm) = self.confirm.take() else {
+            return false;
+        };
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('y' | 'Y') | KeyCode::Enter, _)
+            | (KeyCode::Char('c'), KeyModifiers::CONTROL) => match confirm.action {
+                ConfirmAction::Quit => self.mode = AppMode::Quit,
+                ConfirmAction::Clear => self.clear_console(),
+                ConfirmAction::RepeatCommand => match self.last_command.clone() {
+                    // already confirmed once, don't ask again
+                    Some(Command::Clear) => self.clear_console(),
+                    Some(command) => self.execute(command),
+                    None => {}
+                },
+            },
+            (KeyCode::Char('n' | 'N') | KeyCode::Esc, _) => {}
+            _ => {
+                self.confirm = Some(confirm);
+                return false;
+            }
+        }
+        true
     }
-    // info!(target:"progress-task", "Progress task finished");
-    tx.send(AppEvent::CounterChanged(None))?;
-    Ok(())
-}
 
-/// A background task that logs a log entry for each log level every second.
-fn background_task() {
-    loop {
-        // error!(target:"background-task", "an error");
-        // warn!(target:"background-task", "a warning");
-        // info!(target:"background-task", "an info");
-        // debug!(target:"background-task", "a debug");
-        // trace!(target:"background-task", "a trace");
-        thread::sleep(Duration::from_millis(1000));
+    fn handle_menu_key(&mut self, key: KeyEvent) -> bool {
+        let Some(menu) = self.context_menu.as_mut() else {
+            return false;
+        };
+        let count = MenuItem::ALL.len();
+        match key.code {
+            KeyCode::Up => menu.selected = (menu.selected + count - 1) % count,
+            KeyCode::Down => menu.selected = (menu.selected + 1) % count,
+            KeyCode::Enter => {
+                let item = MenuItem::ALL[menu.selected];
+                self.run_menu_item(item);
+            }
+            KeyCode::Esc => self.context_menu = None,
+            _ => return false,
+        }
+        true
     }
-}
 
-impl Widget for &mut App {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let text = vec![
-            Line::from(vec![
-                Span::raw("First"),
-                Span::styled("line", Style::new().green().italic()),
-                ".".into(),
-            ]),
-            Line::from("Second line".red()),
-            "Third line".into(),
-        ];
-
-        let [left_col, right_col] =
-            Layout::horizontal([Constraint::Percentage(25), Constraint::Percentage(75)])
-                .areas(area);
-
-        let left_rows = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(vec![
-                Constraint::Percentage(20),
-                Constraint::Percentage(20),
-                Constraint::Percentage(75),
-            ])
-            .split(left_col);
-
-        let right_rows = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(vec![Constraint::Min(1), Constraint::Length(3)])
-            .split(right_col);
-
-        self.console_rect = right_rows[0];
-        self.input_rect = right_rows[1];
-
-        Paragraph::new(text.clone())
-            .block(Block::bordered().title("Logo"))
-            .style(Style::new().white().on_black())
-            .alignment(Alignment::Center)
-            .wrap(Wrap { trim: true })
-            .render(left_rows[0], buf);
-
-        Paragraph::new(text.clone())
-            .block(Block::bordered().title("Session Info"))
-            .style(Style::new().white().on_black())
-            .alignment(Alignment::Center)
-            .wrap(Wrap { trim: false })
-            .render(left_rows[1], buf);
-
-        Paragraph::new(text.clone())
-            .block(Block::bordered().title("Items"))
-            .style(Style::new().white().on_black())
-            .alignment(Alignment::Center)
-            .wrap(Wrap { trim: true })
-            .render(left_rows[2], buf);
-
-        let highlighted_content: Vec<Line> = self
-            .messages
+    /// Hovering highlights an item and clicking runs it; a click anywhere else closes the menu.
+    fn handle_menu_mouse(&mut self, mouse: MouseEvent) {
+        let inner = self.menu_rect.inner(Margin::new(1, 1));
+        let item = self
+            .rect_contains(inner, mouse.row, mouse.column)
+            .then(|| (mouse.row - inner.y) as usize)
+            .filter(|index| *index < MenuItem::ALL.len());
+        match (mouse.kind, item) {
+            (MouseEventKind::Moved | MouseEventKind::Drag(_), Some(index)) => {
+                if let Some(menu) = self.context_menu.as_mut() {
+                    menu.selected = index;
+                }
+            }
+            (MouseEventKind::Down(MouseButton::Left), Some(index)) => {
+                self.run_menu_item(MenuItem::ALL[index])
+            }
+            (MouseEventKind::Down(_), None) => self.context_menu = None,
+            _ => {}
+        }
+    }
+
+    fn run_menu_item(&mut self, item: MenuItem) {
+        self.context_menu = None;
+        match item {
+            MenuItem::Copy => self.copy_selection(Selection::Clipboard),
+            MenuItem::SelectAll => {
+                if let Some(last) = self.messages.back() {
+                    self.selection_start = Some((0, 0));
+                    self.selection_end = Some((
+                        self.messages.len() - 1,
+                        last.text.chars().count().saturating_sub(1),
+                    ));
+                }
+            }
+            MenuItem::Clear => self.request_clear(),
+            MenuItem::Close => {}
+        }
+    }
+
+    fn resize_left_column(&mut self, delta: i16) {
+        self.left_column_percent = self
+            .left_column_percent
+            .saturating_add_signed(delta)
+            .clamp(MIN_LEFT_COLUMN_PERCENT, MAX_LEFT_COLUMN_PERCENT);
+    }
+
+    fn toggle_mouse_capture(&mut self) {
+        self.mouse_released = false;
+        let enabled = !self.mouse_captured;
+        if let Err(err) = set_mouse_capture(enabled) {
+            error!(target: "App", "Failed to toggle mouse capture: {}", err);
+            return;
+        }
+        self.mouse_captured = enabled;
+        if !enabled {
+            // the terminal's own selection takes over
+            self.selection_start = None;
+            self.selection_end = None;
+            self.dragging = false;
+            self.dragging_scrollbar = false;
+            self.hovered = None;
+        }
+    }
+
+    fn toggle_console_mouse(&mut self) {
+        self.mouse_console_only = !self.mouse_console_only;
+        self.show_toast(if self.mouse_console_only {
+            "Mouse captured in the console only".to_string()
+        } else {
+            "Mouse captured everywhere".to_string()
+        });
+    }
+
+    /// Hands the mouse to the terminal after it left the console in console-only mode.
+    /// Terminals only report the mouse while it is captured, so it stays released until
+    /// [`App::recapture_mouse`].
+    fn release_mouse(&mut self) {
+        if !self.mouse_captured || self.mouse_released {
+            return;
+        }
+        if let Err(err) = set_mouse_capture(false) {
+            error!(target: "App", "Failed to release mouse capture: {}", err);
+            return;
+        }
+        self.mouse_released = true;
+        self.hovered = None;
+    }
+
+    fn recapture_mouse(&mut self) {
+        if !std::mem::take(&mut self.mouse_released) {
+            return;
+        }
+        if let Err(err) = set_mouse_capture(true) {
+            error!(target: "App", "Failed to capture mouse: {}", err);
+        }
+    }
+
+    /// Whether the position is over the console or its other pane while split.
+    fn in_console(&self, row: u16, col: u16) -> bool {
+        self.rect_contains(self.console_rect, row, col)
+            || self
+                .split
+                .is_some_and(|pane| self.rect_contains(pane.rect, row, col))
+    }
+
+    /// Returns `false` if the key has no binding while the console is focused.
+    fn handle_console_key(&mut self, key: KeyEvent) -> bool {
+        if self.visual_cursor.is_some() && self.handle_visual_key(key) {
+            return true;
+        }
+        match key.code {
+            KeyCode::Esc => {
+                self.selection_start = None;
+                self.selection_end = None;
+            }
+            // the lower pane, then the upper one, then the input
+            KeyCode::Tab if self.split.is_some_and(|pane| pane.top) => self.swap_console_panes(),
+            KeyCode::Tab => {
+                self.swap_console_panes();
+                self.set_focus(FocusMode::Input);
+            }
+            KeyCode::Char('s') => self.toggle_split(),
+            // in the input these move by word
+            KeyCode::Left if key.modifiers == KeyModifiers::CONTROL => self.resize_left_column(-5),
+            KeyCode::Right if key.modifiers == KeyModifiers::CONTROL => self.resize_left_column(5),
+            KeyCode::Char('p') => self.control_progress(ProgressControl::TogglePause),
+            KeyCode::Char('r') => self.control_progress(ProgressControl::Restart),
+            KeyCode::Char('u') if key.modifiers == KeyModifiers::CONTROL => {
+                self.scroll_console_up(self.half_page())
+            }
+            KeyCode::Char('d') if key.modifiers == KeyModifiers::CONTROL => {
+                self.scroll_console_down(self.half_page())
+            }
+            KeyCode::Up => self.scroll_console_up(self.config.scroll_step),
+            KeyCode::Down => self.scroll_console_down(self.config.scroll_step),
+            KeyCode::End | KeyCode::Char('G') => self.scroll_console_to_bottom(),
+            KeyCode::Char('v') => self.start_visual(),
+            KeyCode::Char('j') => self.move_console_cursor(1),
+            KeyCode::Char('k') => self.move_console_cursor(-1),
+            KeyCode::Char('y') if key.modifiers.is_empty() => self.copy_cursor_line(),
+            KeyCode::Char('y') if key.modifiers == KeyModifiers::CONTROL => self.copy_cursor_line(),
+            KeyCode::Char('.') => self.repeat_command(),
+            KeyCode::Char('n') => self.line_numbers = !self.line_numbers,
+            KeyCode::Char('?') => self.toggle_help(),
+            KeyCode::PageUp => self.scroll_console_up(self.console_height()),
+            KeyCode::PageDown => self.scroll_console_down(self.console_height()),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Returns `false` if the key is neither an app binding nor an editing key of the input.
+    fn handle_input_key(&mut self, event: &Event) -> bool {
+        let Event::Key(key) = event else {
+            return false;
+        };
+        if self.search.is_some() {
+            return self.handle_search_key(*key);
+        }
+        // Ctrl+X Ctrl+E opens the input in an external editor, as in bash
+        let ctrl = key.modifiers == KeyModifiers::CONTROL;
+        if std::mem::take(&mut self.ctrl_x_pending) && ctrl && key.code == KeyCode::Char('e') {
+            self.edit_externally = true;
+            return true;
+        }
+        if ctrl && key.code == KeyCode::Char('x') {
+            self.ctrl_x_pending = true;
+            return true;
+        }
+        self.input_invalid = false;
+        if std::mem::take(&mut self.input_selected) {
+            match key.code {
+                KeyCode::Backspace | KeyCode::Delete => {
+                    self.clear_input();
+                    return true;
+                }
+                KeyCode::Char(_)
+                    if !key
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                {
+                    self.clear_input()
+                }
+                // anything else keeps the text and just drops the selection
+                _ => {}
+            }
+        }
+        match key.code {
+            KeyCode::Char('r') if key.modifiers == KeyModifiers::CONTROL => {
+                self.search = Some(ReverseSearch {
+                    saved: self.input.value().to_string(),
+                    ..Default::default()
+                });
+            }
+            KeyCode::Enter
+                if self.multiline
+                    && key.modifiers.intersects(
+                        KeyModifiers::SHIFT | KeyModifiers::ALT | KeyModifiers::CONTROL,
+                    ) =>
+            {
+                self.input_lines.push(self.input.value().into());
+                self.input.reset();
+            }
+            KeyCode::Enter
+                if !self.config.submit_empty
+                    && self.input.value().is_empty()
+                    && self.input_lines.is_empty() =>
+            {
+                match self.config.empty_enter {
+                    EmptyEnter::Ignore => {}
+                    EmptyEnter::FocusConsole => self.set_focus(FocusMode::Console),
+                }
+            }
+            KeyCode::Enter => {
+                if let Err(reason) = self.validate_input() {
+                    warn!(target: "App", "Input rejected: {}", reason);
+                    self.show_error(reason);
+                    self.input_invalid = true;
+                    return true;
+                }
+                for line in self.input_lines.clone() {
+                    self.submit_line(line);
+                }
+                self.submit_line(self.input.value().into());
+                match self.config.submit_behavior {
+                    SubmitBehavior::Clear => self.clear_input(),
+                    SubmitBehavior::Keep => {}
+                    SubmitBehavior::SelectAll => self.input_selected = true,
+                }
+                // a submission starts fresh rather than leaving a stale selection behind
+                self.selection_start = None;
+                self.selection_end = None;
+                debug!("{:?}", self.messages);
+            }
+            KeyCode::Backspace if self.input.cursor() == 0 && !self.input_lines.is_empty() => {
+                // join the current line onto the end of the previous one
+                let previous = self.input_lines.pop().unwrap_or_default();
+                let cursor = previous.chars().count();
+                self.input = Input::new(previous + self.input.value()).with_cursor(cursor);
+            }
+            KeyCode::F(2) => self.toggle_multiline(),
+            KeyCode::Up => {
+                if let Some(line) = self.history.previous(self.input.value()) {
+                    self.input = Input::new(line.to_string());
+                }
+            }
+            KeyCode::Down => {
+                if let Some(line) = self.history.next() {
+                    self.input = Input::new(line.to_string());
+                }
+            }
+            // Esc only ever cancels; quitting has its own binding
+            KeyCode::Esc => {
+                self.selection_start = None;
+                self.selection_end = None;
+            }
+            KeyCode::Char(':') if self.input.value().is_empty() => {
+                self.command = Some(Input::default());
+                self.status_message = None;
+            }
+            KeyCode::Char('u') if key.modifiers == KeyModifiers::CONTROL => {
+                delete_till_start(&mut self.input);
+            }
+            // unlike Ctrl+U this drops the whole message, wherever the cursor is
+            KeyCode::Char('u') if key.modifiers == KeyModifiers::ALT => {
+                self.clear_input();
+                self.scroll = 0;
+            }
+            _ => match edit_request(key).or_else(|| to_input_request(event)) {
+                Some(request) => {
+                    self.input.handle(request);
+                }
+                None => return false,
+            },
+        }
+        true
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) -> bool {
+        let Some(search) = self.search.as_mut() else {
+            return false;
+        };
+        match (key.code, key.modifiers) {
+            (KeyCode::Enter, _) => {
+                self.search = None;
+                self.input = Input::new(self.input.value().to_string());
+            }
+            (KeyCode::Esc, _) | (KeyCode::Char('g'), KeyModifiers::CONTROL) => {
+                let saved = std::mem::take(&mut search.saved);
+                self.search = None;
+                self.input = Input::new(saved);
+            }
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                let before = search.matched.unwrap_or(usize::MAX);
+                self.search_history(before);
+            }
+            (KeyCode::Backspace, _) => {
+                search.query.pop();
+                self.search_history(usize::MAX);
+            }
+            (KeyCode::Char(ch), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                search.query.push(ch);
+                self.search_history(usize::MAX);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Shows the newest history entry older than `before` matching the search query, leaving
+    /// the current match in place when there is none.
+    fn search_history(&mut self, before: usize) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        match self.history.search(&search.query, before) {
+            Some((index, entry)) => {
+                search.matched = Some(index);
+                search.failing = false;
+                let cursor = entry
+                    .find(&search.query)
+                    .map_or(0, |start| entry[..start].chars().count());
+                self.input = Input::new(entry.to_string()).with_cursor(cursor);
+            }
+            None => search.failing = true,
+        }
+    }
+
+    /// Empties the input, including any finished multiline lines.
+    fn clear_input(&mut self) {
+        self.input.reset();
+        self.input_lines.clear();
+    }
+
+    /// Runs the validator over every line about to be submitted, stopping at the first error.
+    fn validate_input(&self) -> Result<(), String> {
+        self.input_lines
             .iter()
-            .enumerate()
-            .flat_map(|(line_index, message)| {
-                let mut spans = Vec::new();
-                let chars: Vec<char> = message.chars().collect();
-                let mut in_selection = false;
-
-                for (char_index, &ch) in chars.iter().enumerate() {
-                    if let Some((start_line, start_col)) = self.selection_start {
-                        if let Some((end_line, end_col)) = self.selection_end {
-                            if (line_index == start_line && char_index >= start_col)
-                                || (line_index == end_line && char_index <= end_col)
-                                || (line_index > start_line && line_index < end_line)
-                            {
-                                in_selection = true;
-                            } else {
-                                in_selection = false;
-                            }
-                        }
+            .map(String::as_str)
+            .chain([self.input.value()])
+            .try_for_each(|line| (self.validator)(line))
+    }
+
+    /// Adds a line typed by the user to the console, the history and any sinks.
+    fn submit_line(&mut self, line: String) {
+        self.history.push(&line);
+        for sink in &mut self.sinks {
+            if let Err(err) = sink.send(&line) {
+                error!(target: "App", "Failed to emit message: {}", err);
+            }
+        }
+        self.push_message(ConsoleLine::input(line));
+    }
+
+    /// Appends a console line, dropping the oldest ones once `max_messages` is reached.
+    fn push_message(&mut self, line: ConsoleLine) {
+        self.messages.push_back(line);
+        let excess = self
+            .messages
+            .len()
+            .saturating_sub(self.config.max_messages.max(1));
+        if excess == 0 {
+            return;
+        }
+        self.messages.drain(..excess);
+        // keep the view on the same lines rather than jumping forward
+        self.console_scroll = self.console_scroll.saturating_sub(excess);
+        self.console_cursor = self
+            .console_cursor
+            .map(|cursor| cursor.saturating_sub(excess));
+        self.visual_cursor = self
+            .visual_cursor
+            .map(|(line, col)| (line.saturating_sub(excess), col));
+        if let Some(pane) = self.split.as_mut() {
+            pane.scroll = pane.scroll.saturating_sub(excess);
+        }
+        let shift = |(line, col): (usize, usize)| (line.checked_sub(excess), col);
+        match (
+            self.selection_start.map(shift),
+            self.selection_end.map(shift),
+        ) {
+            (Some((Some(start), start_col)), Some((Some(end), end_col))) => {
+                self.selection_start = Some((start, start_col));
+                self.selection_end = Some((end, end_col));
+            }
+            // one end was evicted: clamp it to the start of the oldest remaining line
+            (Some((start, start_col)), Some((end, end_col))) if start.or(end).is_some() => {
+                self.selection_start = Some(start.map_or((0, 0), |line| (line, start_col)));
+                self.selection_end = Some(end.map_or((0, 0), |line| (line, end_col)));
+            }
+            _ => {
+                self.selection_start = None;
+                self.selection_end = None;
+            }
+        }
+    }
+
+    /// Rewrites the newest console line, keeping its level and time. Appends instead when the
+    /// console is empty or in [`ConsoleMode::Append`]. A selection reaching the old text is
+    /// dropped since its columns no longer mean anything.
+    pub fn update_last_line(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        let last = self.messages.len().checked_sub(1);
+        let (Some(last), ConsoleMode::Editable) = (last, self.config.console_mode) else {
+            self.push_message(text.into());
+            return;
+        };
+        let width = text.chars().count();
+        self.messages[last].text = text;
+        if self
+            .selection_range()
+            .is_some_and(|(_, (end_line, _))| end_line >= last)
+        {
+            self.selection_start = None;
+            self.selection_end = None;
+        }
+        if let Some((line, col)) = self.visual_cursor.as_mut() {
+            if *line == last {
+                *col = (*col).min(width.saturating_sub(1));
+            }
+        }
+    }
+
+    fn toggle_multiline(&mut self) {
+        self.multiline = !self.multiline;
+        if !self.multiline && !self.input_lines.is_empty() {
+            // fold the finished lines back into the single input line
+            let mut line = self.input_lines.drain(..).collect::<Vec<_>>().join(" ");
+            line.push(' ');
+            let cursor = line.chars().count() + self.input.cursor();
+            self.input = Input::new(line + self.input.value()).with_cursor(cursor);
+        }
+    }
+
+    /// Puts the input cursor at the clicked character of the line being edited, or at the end
+    /// for a click past the text. Clicks on finished multiline rows or the border are ignored.
+    fn move_input_cursor_to(&mut self, row: u16, col: u16) {
+        let border = self.border_width();
+        let line_row = self.input_rect.y + border + self.visible_input_lines().len() as u16;
+        let text_x = self.input_rect.x + border + self.prompt_width();
+        if row != line_row || col < text_x || col >= self.input_rect.right().saturating_sub(border)
+        {
+            return;
+        }
+        let target = (col - text_x) as usize + self.scroll;
+        let mut used = 0;
+        let mut cursor = self.input.value().chars().count();
+        for (index, ch) in self.input.value().chars().enumerate() {
+            let width = char_width(ch, false);
+            // the left half of a glyph puts the cursor before it, the right half after it
+            if target < used + width.div_ceil(2) {
+                cursor = index;
+                break;
+            }
+            used += width;
+        }
+        self.input.handle(InputRequest::SetCursor(cursor));
+    }
+
+    /// The prompt drawn before the line being edited, with its tokens filled in if
+    /// `input_prompt_template` is set.
+    fn prompt(&self) -> Cow<'_, str> {
+        let prompt = self.config.input_prompt.as_str();
+        if !self.config.input_prompt_template || !prompt.contains('{') {
+            return Cow::Borrowed(prompt);
+        }
+        let mut filled = String::new();
+        let mut rest = prompt;
+        while let Some(open) = rest.find('{') {
+            filled.push_str(&rest[..open]);
+            rest = &rest[open..];
+            let Some(close) = rest.find('}') else {
+                break;
+            };
+            match self.prompt_token(&rest[1..close]) {
+                Some(value) => filled.push_str(&value),
+                None => filled.push_str(&rest[..=close]),
+            }
+            rest = &rest[close + 1..];
+        }
+        filled.push_str(rest);
+        Cow::Owned(filled)
+    }
+
+    /// Value of a `{token}` in a prompt template, `None` for unknown tokens.
+    fn prompt_token(&self, token: &str) -> Option<String> {
+        Some(match token {
+            // `Info` rather than `INFO`
+            "level" => {
+                let level = self.log_level.as_str();
+                level[..1].to_string() + &level[1..].to_lowercase()
+            }
+            "tab" => self.config.log_tabs.get(self.selected_tab)?.name.clone(),
+            "focus" => self.focus_mode.to_string(),
+            "messages" => self.messages.len().to_string(),
+            _ => return None,
+        })
+    }
+
+    /// Display width of the prompt drawn before the line being edited.
+    fn prompt_width(&self) -> u16 {
+        Span::raw(self.prompt()).width() as u16
+    }
+
+    /// Finished multiline lines that fit in the input box above the line being edited.
+    fn visible_input_lines(&self) -> &[String] {
+        let skip = self.input_lines.len().saturating_sub(MAX_INPUT_LINES - 1);
+        &self.input_lines[skip..]
+    }
+
+    /// Handles keys while the command line is open. Editing keys go to the command line.
+    fn handle_command_key(&mut self, event: &Event) -> bool {
+        let (Event::Key(key), Some(command)) = (event, self.command.as_mut()) else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let line = command.value().to_string();
+                self.command = None;
+                match line.parse::<Command>() {
+                    Ok(command) => self.execute(command),
+                    Err(err) => self.show_error(err.to_string()),
+                }
+            }
+            KeyCode::Esc => self.command = None,
+            KeyCode::Backspace if command.value().is_empty() => self.command = None,
+            _ => match to_input_request(event) {
+                Some(request) => {
+                    command.handle(request);
+                }
+                None => return false,
+            },
+        }
+        true
+    }
+
+    fn execute(&mut self, command: Command) {
+        info!(target: "App", "Executing command {:?}", command);
+        match &command {
+            Command::Quit => self.mode = AppMode::Quit,
+            Command::Clear => self.request_clear(),
+            Command::Export => self.export_console(),
+            Command::Echo(text) => self.push_message(text.as_str().into()),
+            Command::Theme(name) => match Theme::named(name) {
+                Some(theme) => self.theme = theme,
+                None => {
+                    self.show_error(format!("Unknown theme: {name}"));
+                    return;
+                }
+            },
+            Command::Level(level) => {
+                self.set_log_level(*level);
+                self.status_message = Some(format!("log level: {level}"));
+            }
+        }
+        self.last_command = Some(command);
+    }
+
+    /// Runs the last successful command again, asking first if it is destructive and
+    /// `confirm_repeat` is set.
+    fn repeat_command(&mut self) {
+        let Some(command) = self.last_command.clone() else {
+            self.show_toast("No command to repeat".to_string());
+            return;
+        };
+        if command.is_destructive() && self.config.confirm_repeat {
+            self.confirm = Some(Confirm {
+                prompt: format!("Repeat :{command}?"),
+                action: ConfirmAction::RepeatCommand,
+            });
+        } else {
+            self.execute(command);
+        }
+    }
+
+    fn set_log_level(&mut self, level: LevelFilter) {
+        set_default_level(level);
+        self.states = log_tab_states(&self.config.log_tabs, level);
+        self.log_level = level;
+    }
+
+    /// Steps through Error, Warn, Info, Debug and Trace, then back to Error.
+    fn cycle_log_level(&mut self) {
+        let level = match self.log_level {
+            LevelFilter::Error => LevelFilter::Warn,
+            LevelFilter::Warn => LevelFilter::Info,
+            LevelFilter::Info => LevelFilter::Debug,
+            LevelFilter::Debug => LevelFilter::Trace,
+            LevelFilter::Trace | LevelFilter::Off => LevelFilter::Error,
+        };
+        self.set_log_level(level);
+        self.show_toast(format!("log level: {level}"));
+    }
+
+    /// Logs what a bug report needs to reproduce the current view.
+    #[cfg(feature = "debug-state")]
+    fn dump_state(&mut self) {
+        tracing::debug!(
+            focus_mode = ?self.focus_mode,
+            mode = ?self.mode,
+            selection_start = ?self.selection_start,
+            selection_end = ?self.selection_end,
+            console_scroll = self.console_scroll,
+            split_scroll = ?self.split.map(|pane| pane.scroll),
+            input_scroll = self.scroll,
+            follow = self.follow,
+            messages = self.messages.len(),
+            selected_tab = self.selected_tab,
+            "app state"
+        );
+        self.show_toast("App state logged".to_string());
+    }
+
+    /// Splits the console into a pinned upper pane at the current position and a lower pane
+    /// that follows new output, or joins it back into the active pane.
+    fn toggle_split(&mut self) {
+        self.split = match self.split {
+            Some(_) => None,
+            None => {
+                let pinned = ConsolePane {
+                    rect: Rect::default(),
+                    scroll: self.console_scroll,
+                    follow: false,
+                    top: true,
+                };
+                self.follow = true;
+                Some(pinned)
+            }
+        };
+    }
+
+    /// Makes the other half of the split console the one that keys, clicks and the
+    /// scrollbar act on.
+    fn swap_console_panes(&mut self) {
+        let Some(pane) = self.split.as_mut() else {
+            return;
+        };
+        std::mem::swap(&mut pane.rect, &mut self.console_rect);
+        std::mem::swap(&mut pane.scroll, &mut self.console_scroll);
+        std::mem::swap(&mut pane.follow, &mut self.follow);
+        pane.top = !pane.top;
+        self.scrollbar_rect = self.scrollbar_track();
+        self.dirty = true;
+    }
+
+    /// Empties the console, dropping any selection and scroll position.
+    fn clear_console(&mut self) {
+        self.messages.clear();
+        self.console_cursor = None;
+        self.visual_cursor = None;
+        self.selection_start = None;
+        self.selection_end = None;
+        self.console_scroll = 0;
+        self.follow = true;
+    }
+
+    fn unhandled_key(&mut self) {
+        match self.config.bell {
+            BellStyle::None => {}
+            BellStyle::Visual => self.flash = true,
+            BellStyle::Audible => {
+                let mut stdout = io::stdout();
+                let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+            }
+        }
+    }
+
+    fn rect_contains(&self, rect: Rect, row: u16, col: u16) -> bool {
+        row >= rect.y && row < rect.y + rect.height && col >= rect.x && col < rect.x + rect.width
+    }
+
+    /// The panel a click at this position would focus.
+    fn panel_at(&self, row: u16, col: u16) -> Option<FocusMode> {
+        let in_console = self.rect_contains(self.console_rect, row, col)
+            || self
+                .split
+                .is_some_and(|pane| self.rect_contains(pane.rect, row, col));
+        if self.rect_contains(self.input_rect, row, col) {
+            Some(FocusMode::Input)
+        } else if in_console {
+            Some(FocusMode::Console)
+        } else {
+            None
+        }
+    }
+
+    /// The console area inside its border.
+    fn console_inner(&self) -> Rect {
+        if self.compact {
+            // keep the last column for the scrollbar, which sits on the border otherwise
+            Rect {
+                width: self.console_rect.width.saturating_sub(1),
+                ..self.console_rect
+            }
+        } else {
+            self.console_rect.inner(Margin::new(1, 1))
+        }
+    }
+
+    /// Width of the panel borders on each side: 1, or 0 in compact mode.
+    fn border_width(&self) -> u16 {
+        u16::from(!self.compact)
+    }
+
+    /// Columns left of the console text for line numbers and timestamps.
+    fn gutter_width(&self) -> u16 {
+        self.line_number_width() + self.timestamp_width()
+    }
+
+    /// Columns taken by line numbers, including a separating space.
+    fn line_number_width(&self) -> u16 {
+        if self.line_numbers {
+            self.messages.len().max(1).to_string().len() as u16 + 1
+        } else {
+            0
+        }
+    }
+
+    /// Columns taken by the timestamp column, including a separating space. Measured on the
+    /// current time, so formats with variable-width fields are cut to fit.
+    fn timestamp_width(&self) -> u16 {
+        self.config
+            .timestamp_format
+            .as_deref()
+            .and_then(|format| format_time(&Local::now(), format))
+            .map_or(0, |sample| Span::raw(sample).width() as u16 + 1)
+    }
+
+    /// Columns `message` wraps at in a console text area `width` columns wide. Submitted
+    /// lines are indented by `input_echo_prefix`, leaving at least one column of text.
+    fn text_width(&self, message: &ConsoleLine, width: usize) -> usize {
+        if message.level == LineLevel::Input {
+            width
+                .saturating_sub(Span::raw(self.config.input_echo_prefix.as_str()).width())
+                .max(1)
+        } else {
+            width
+        }
+    }
+
+    /// Text put before a copied line: its timestamp if `copy_timestamps` is set, then the
+    /// input prefix on submitted lines if `copy_input_prefix` is set.
+    fn copy_prefix(&self, line: &ConsoleLine) -> String {
+        let mut prefix = match &self.config.timestamp_format {
+            Some(format) if self.config.copy_timestamps => line
+                .timestamp(format)
+                .map_or_else(String::new, |timestamp| timestamp + " "),
+            _ => String::new(),
+        };
+        if self.config.copy_input_prefix && line.level == LineLevel::Input {
+            prefix.push_str(&self.config.input_echo_prefix);
+        }
+        prefix
+    }
+
+    /// The part of the console where message text is drawn, right of the gutter.
+    fn console_text_area(&self) -> Rect {
+        let inner = self.console_inner();
+        let gutter = self.gutter_width().min(inner.width);
+        Rect {
+            x: inner.x + gutter,
+            width: inner.width - gutter,
+            ..inner
+        }
+    }
+
+    fn console_height(&self) -> usize {
+        self.console_inner().height as usize
+    }
+
+    /// Rows moved by `Ctrl+D`/`Ctrl+U`, at least one.
+    fn half_page(&self) -> usize {
+        (self.console_height() / 2).max(1)
+    }
+
+    /// Converts a screen position to a (line, column) position in `messages`. Long messages
+    /// wrap over several rows, so the visual row is walked down from `console_scroll`.
+    /// Positions below the last message map to the start of the line after it.
+    fn console_position(&self, row: u16, col: u16) -> (usize, usize) {
+        let inner = self.console_text_area();
+        let width = inner.width.max(1) as usize;
+        let cjk = self.config.cjk_ambiguous_width;
+        let mut visual_row = row.saturating_sub(inner.y) as usize;
+        let col = (col.saturating_sub(inner.x) as usize).min(width - 1);
+        for (index, message) in self.messages.iter().enumerate().skip(self.console_scroll) {
+            let text_width = self.text_width(message, width);
+            // clicks on the input prefix land on the first character
+            let relative_col = col.saturating_sub(width - text_width);
+            let starts = row_starts(&message.text, text_width, cjk);
+            if let Some(&row_start) = starts.get(visual_row) {
+                let chars: Vec<char> = message.text.chars().collect();
+                let row_end = starts.get(visual_row + 1).copied();
+                let mut used = 0;
+                for (offset, ch) in chars[row_start..row_end.unwrap_or(chars.len())]
+                    .iter()
+                    .enumerate()
+                {
+                    used += char_width(*ch, cjk);
+                    // a click on either half of a wide glyph selects it
+                    if relative_col < used {
+                        return (index, row_start + offset);
                     }
+                }
+                return match row_end {
+                    // past the end of a wrapped row: the row's last character
+                    Some(row_end) => (index, row_end.saturating_sub(1).max(row_start)),
+                    None => (index, chars.len() + relative_col - used),
+                };
+            }
+            visual_row -= starts.len();
+        }
+        (self.messages.len(), 0)
+    }
 
-                    let span = if in_selection {
-                        Span::styled(
-                            ch.to_string(),
-                            Style::default().fg(Color::Yellow).bg(Color::Blue),
-                        )
-                    } else {
-                        Span::raw(ch.to_string())
-                    };
-                    spans.push(span);
+    /// Largest `console_scroll` that still fills the console, accounting for wrapped lines.
+    fn max_console_scroll(&self) -> usize {
+        let inner = self.console_text_area();
+        let mut rows = 0;
+        for (index, message) in self.messages.iter().enumerate().rev() {
+            rows += wrapped_rows(
+                &message.text,
+                self.text_width(message, inner.width as usize),
+                self.config.cjk_ambiguous_width,
+            );
+            if rows > inner.height as usize {
+                return index + 1;
+            }
+        }
+        0
+    }
+
+    fn scroll_console_up(&mut self, lines: usize) {
+        self.set_console_scroll(self.console_scroll.saturating_sub(lines));
+    }
+
+    fn scroll_console_down(&mut self, lines: usize) {
+        self.set_console_scroll(self.console_scroll + lines);
+    }
+
+    /// Jumps to the newest message and resumes following new output.
+    fn scroll_console_to_bottom(&mut self) {
+        self.set_console_scroll(usize::MAX);
+    }
+
+    /// Scrolls to `position`, following new output only while the bottom is in view.
+    fn set_console_scroll(&mut self, position: usize) {
+        let max_scroll = self.max_console_scroll();
+        self.console_scroll = position.min(max_scroll);
+        self.follow = self.console_scroll == max_scroll;
+    }
+
+    /// Moves the console cursor by `delta` messages, starting from the top visible message,
+    /// and scrolls to keep it in view.
+    fn move_console_cursor(&mut self, delta: isize) {
+        let Some(last) = self.messages.len().checked_sub(1) else {
+            return;
+        };
+        let cursor = match self.console_cursor {
+            Some(cursor) => cursor.saturating_add_signed(delta).min(last),
+            None => self.console_scroll.min(last),
+        };
+        self.console_cursor = Some(cursor);
+        self.scroll_to_message(cursor);
+    }
+
+    /// Scrolls the least needed for every row of message `index` to be visible.
+    fn scroll_to_message(&mut self, index: usize) {
+        if index < self.console_scroll {
+            self.set_console_scroll(index);
+            return;
+        }
+        let inner = self.console_text_area();
+        let rows = |message: &ConsoleLine| {
+            wrapped_rows(
+                &message.text,
+                self.text_width(message, inner.width as usize),
+                self.config.cjk_ambiguous_width,
+            )
+        };
+        let mut scroll = self.console_scroll;
+        let mut used: usize = self.messages.range(scroll..=index).map(rows).sum();
+        while used > inner.height as usize && scroll < index {
+            used -= rows(&self.messages[scroll]);
+            scroll += 1;
+        }
+        self.set_console_scroll(scroll);
+    }
+
+    /// Enters visual mode at the start of the console cursor line, or the top visible one,
+    /// with an empty selection anchored there.
+    fn start_visual(&mut self) {
+        if self.messages.is_empty() {
+            return;
+        }
+        let line = self
+            .console_cursor
+            .unwrap_or(self.console_scroll)
+            .min(self.messages.len() - 1);
+        self.visual_cursor = Some((line, 0));
+        self.selection_start = Some((line, 0));
+        self.selection_end = Some((line, 0));
+    }
+
+    /// Keys of visual mode: arrows or `hjkl` move the selection end, `y` copies and `Esc`
+    /// cancels. Returns `false` for keys left to the normal console bindings.
+    fn handle_visual_key(&mut self, key: KeyEvent) -> bool {
+        let Some((line, col)) = self.visual_cursor else {
+            return false;
+        };
+        let line_len = |app: &App, line: usize| app.messages[line].text.chars().count();
+        let last_line = self.messages.len().saturating_sub(1);
+        let cursor = match key.code {
+            KeyCode::Esc => {
+                self.visual_cursor = None;
+                self.selection_start = None;
+                self.selection_end = None;
+                return true;
+            }
+            KeyCode::Char('y') => {
+                self.copy_selection(Selection::Clipboard);
+                self.visual_cursor = None;
+                return true;
+            }
+            KeyCode::Left | KeyCode::Char('h') if col > 0 => (line, col - 1),
+            // wrap to the end of the previous line
+            KeyCode::Left | KeyCode::Char('h') if line > 0 => {
+                (line - 1, line_len(self, line - 1).saturating_sub(1))
+            }
+            KeyCode::Right | KeyCode::Char('l') if col + 1 < line_len(self, line) => {
+                (line, col + 1)
+            }
+            KeyCode::Right | KeyCode::Char('l') if line < last_line => (line + 1, 0),
+            KeyCode::Up | KeyCode::Char('k') if line > 0 => (
+                line - 1,
+                col.min(line_len(self, line - 1).saturating_sub(1)),
+            ),
+            KeyCode::Down | KeyCode::Char('j') if line < last_line => (
+                line + 1,
+                col.min(line_len(self, line + 1).saturating_sub(1)),
+            ),
+            // at an edge already
+            KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Char('h' | 'j' | 'k' | 'l') => (line, col),
+            _ => return false,
+        };
+        self.visual_cursor = Some(cursor);
+        self.selection_end = Some(cursor);
+        self.console_cursor = Some(cursor.0);
+        self.scroll_to_message(cursor.0);
+        true
+    }
+
+    /// Copies the whole message under the console cursor, or the top visible one, to the
+    /// clipboard.
+    fn copy_cursor_line(&mut self) {
+        let index = self.console_cursor.unwrap_or(self.console_scroll);
+        if let Some(message) = self.messages.get(index) {
+            let text = self.copy_prefix(message) + &message.text;
+            self.copy_text(text, Selection::Clipboard);
+        }
+    }
+
+    /// Starts a selection at a console click, or widens it to the word or the line under the
+    /// pointer when this is the second or third click in quick succession on the same cell.
+    fn click_console(&mut self, row: u16, col: u16) {
+        let now = Instant::now();
+        let clicks = match self.last_click {
+            Some((at, cell, count))
+                if cell == (row, col) && now.duration_since(at) <= MULTI_CLICK_INTERVAL =>
+            {
+                count % 3 + 1
+            }
+            _ => 1,
+        };
+        self.last_click = Some((now, (row, col), clicks));
+        let (line, col) = self.console_position(row, col);
+        let chars: Vec<char> = self
+            .messages
+            .get(line)
+            .map_or_else(Vec::new, |message| message.text.chars().collect());
+        let range = match clicks {
+            2 if col < chars.len() => {
+                // a run of word characters, or of whitespace when clicking between words
+                let blank = chars[col].is_whitespace();
+                let same = |ch: &char| ch.is_whitespace() == blank;
+                let start = col - chars[..col].iter().rev().take_while(|ch| same(ch)).count();
+                let end = col + chars[col..].iter().take_while(|ch| same(ch)).count() - 1;
+                Some((start, end))
+            }
+            3 if line < self.messages.len() => Some((0, chars.len().saturating_sub(1))),
+            _ => None,
+        };
+        match range {
+            Some((start, end)) => {
+                self.selection_start = Some((line, start));
+                self.selection_end = Some((line, end));
+                self.dragging = false;
+                if self.config.copy_on_select {
+                    self.copy_selection(Selection::Primary);
                 }
+            }
+            None => {
+                self.selection_start = Some((line, col));
+                self.selection_end = self.selection_start;
+                self.dragging = true;
+            }
+        }
+    }
+
+    /// Moves the selection end to the mouse position, or to the nearest visible row while the
+    /// mouse is above or below the console.
+    fn extend_selection_to(&mut self, row: u16, col: u16) {
+        let inner = self.console_inner();
+        let row = row.clamp(
+            inner.top(),
+            inner.bottom().saturating_sub(1).max(inner.top()),
+        );
+        self.selection_end = Some(self.console_position(row, col));
+    }
+
+    /// Scrolls the console to the position matching `row` on the scrollbar track.
+    fn scroll_console_to_track(&mut self, row: u16) {
+        let track = self.scrollbar_rect;
+        let offset = row
+            .saturating_sub(track.y)
+            .min(track.height.saturating_sub(1)) as usize;
+        let max_scroll = self.max_console_scroll();
+        let track_length = (track.height as usize).saturating_sub(1).max(1);
+        self.set_console_scroll((offset * max_scroll + track_length / 2) / track_length);
+    }
+
+    /// The selection ordered so that the start comes before the end.
+    fn selection_range(&self) -> Option<((usize, usize), (usize, usize))> {
+        let (start, end) = (self.selection_start?, self.selection_end?);
+        Some(if start <= end {
+            (start, end)
+        } else {
+            (end, start)
+        })
+    }
 
-                vec![Line::from(spans)]
+    fn is_selected(&self, line: usize, col: usize) -> bool {
+        self.selection_range()
+            .is_some_and(|(start, end)| (line, col) >= start && (line, col) <= end)
+    }
+
+    /// The selected console text, one line per message, joined per `copy_line_ending`.
+    fn selected_text(&self) -> Option<String> {
+        let ((start_line, start_col), (end_line, end_col)) = self.selection_range()?;
+        let end_line = end_line.min(self.messages.len().checked_sub(1)?);
+        let lines: Vec<String> = (start_line..=end_line)
+            .map(|line| {
+                let from = if line == start_line { start_col } else { 0 };
+                let to = if line == end_line {
+                    end_col + 1
+                } else {
+                    usize::MAX
+                };
+                let message = &self.messages[line];
+                let text: String = message
+                    .text
+                    .chars()
+                    .skip(from)
+                    .take(to.saturating_sub(from))
+                    .collect();
+                self.copy_prefix(message) + &text
             })
             .collect();
+        Some(self.config.copy_line_ending.join(&lines))
+    }
 
-        Paragraph::new(highlighted_content)
-            .block(
-                Block::bordered()
-                    .title("Console")
-                    .style(match self.focus_mode {
-                        FocusMode::Input => Style::default().fg(Color::White),
-                        FocusMode::Console => Style::default().fg(Color::Yellow),
-                    }),
-            )
-            .style(Style::default().fg(Color::White))
-            .alignment(Alignment::Left)
-            .wrap(Wrap { trim: false })
-            .render(self.console_rect, buf);
-
-        let width = self.input_rect.width.max(3) - 3; // keep 2 for borders and 1 for cursor
-        self.scroll = self.input.visual_scroll(width as usize);
-        Paragraph::new(self.input.value())
-            .style(Style::default().fg(Color::White))
-            .scroll((0, self.scroll as u16))
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .style(match self.focus_mode {
-                        FocusMode::Input => Style::default().fg(Color::Yellow),
-                        FocusMode::Console => Style::default().fg(Color::White),
-                    })
-                    .title("Input"),
-            )
-            .render(self.input_rect, buf);
+    /// Copies the console selection. A clipboard copy clears the selection once it succeeds
+    /// if `clear_selection_after_copy` is set; copying to the primary selection never does.
+    fn copy_selection(&mut self, selection: Selection) {
+        if let Some(text) = self.selected_text() {
+            let sent = self.copy_text(text, selection);
+            self.clear_selection_on_copy =
+                sent && selection == Selection::Clipboard && self.config.clear_selection_after_copy;
+        }
+    }
+
+    /// Copies every console line to the clipboard. Unlike `Ctrl+C` this ignores the
+    /// selection entirely, so it works without selecting anything first.
+    fn copy_console(&mut self) {
+        if self.messages.is_empty() {
+            return;
+        }
+        self.copy_text(self.console_text(), Selection::Clipboard);
+    }
+
+    /// Every console message as copied, with timestamps and line endings following config.
+    fn console_text(&self) -> String {
+        let lines: Vec<String> = self
+            .messages
+            .iter()
+            .map(|message| self.copy_prefix(message) + &message.text)
+            .collect();
+        self.config.copy_line_ending.join(&lines)
+    }
+
+    /// Saves the console to `console-<date>-<time>.txt` in the data directory and reports the
+    /// path, or the failure, in a toast.
+    fn export_console(&mut self) {
+        let name = Local::now().format("console-%Y%m%d-%H%M%S.txt").to_string();
+        let path = config::get_data_dir().join(name);
+        let result = std::fs::write(&path, self.console_text()).map_err(AppError::Io);
+        match result {
+            Ok(()) => {
+                info!(target: "App", "Exported console to {}", path.display());
+                self.show_toast(format!("Saved {}", path.display()));
+            }
+            Err(err) => {
+                error!(target: "App", "Failed to export console: {}", err);
+                self.show_error(format!("Export failed: {err}"));
+            }
+        }
+    }
+
+    /// Hands `text` to the clipboard worker, returning whether it was sent. The outcome
+    /// arrives later as [`AppEvent::CopyResult`].
+    fn copy_text(&mut self, text: String, selection: Selection) -> bool {
+        let result = match &self.clipboard {
+            Some(clipboard) => clipboard.copy(text, selection),
+            None => Err(AppError::TaskChannelClosed("clipboard worker")),
+        };
+        if let Err(err) = &result {
+            error!(target: "App", "Failed to copy: {}", err);
+            self.show_error(format!("Copy failed: {err}"));
+        }
+        result.is_ok()
+    }
+
+    fn show_toast(&mut self, message: String) {
+        self.show_toast_styled(message, self.theme.panel());
+    }
+
+    fn show_error(&mut self, message: String) {
+        self.show_toast_styled(message, self.theme.console_line(LineLevel::Error));
+    }
+
+    /// Shows `message` in the footer for `toast_duration_ms`, replacing any earlier toast.
+    fn show_toast_styled(&mut self, message: String, style: Style) {
+        let duration = Duration::from_millis(self.config.toast_duration_ms);
+        self.toast = Some((message, Instant::now() + duration, style));
+    }
+
+    /// Track of the console scrollbar on the right border; empty while all messages fit.
+    fn scrollbar_track(&self) -> Rect {
+        if self.max_console_scroll() == 0 {
+            return Rect::default();
+        }
+        Rect {
+            x: self.console_rect.right().saturating_sub(1),
+            width: 1,
+            ..self.console_inner()
+        }
+    }
+
+    fn next_tab(&mut self) {
+        self.selected_tab = (self.selected_tab + 1) % self.states.len().max(1);
+    }
+
+    fn previous_tab(&mut self) {
+        let count = self.states.len().max(1);
+        self.selected_tab = (self.selected_tab + count - 1) % count;
+    }
+}
+
+/// Bounds for resizing the left column with Ctrl+Left/Ctrl+Right.
+const MIN_LEFT_COLUMN_PERCENT: u16 = 10;
+const MAX_LEFT_COLUMN_PERCENT: u16 = 60;
+
+/// Status bar spinner, advanced once per tick while any task is active.
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How long before an idle timeout the status bar starts counting down.
+const IDLE_COUNTDOWN: Duration = Duration::from_secs(10);
+
+/// Longest gap between clicks that still counts as a double or triple click.
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Quiet time after a resize before laying out for the new size, so that dragging a window
+/// edge redraws once it pauses rather than for every intermediate size.
+const RESIZE_SETTLE: Duration = Duration::from_millis(30);
+/// Longest a stream of resizes holds back drawing.
+const RESIZE_MAX_DELAY: Duration = Duration::from_millis(150);
+
+/// Tallest the input box grows to in multiline mode, in text rows.
+const MAX_INPUT_LINES: usize = 5;
+
+/// Drops all but the newest of each kind of state update in `events`, keeping everything else
+/// in order, so a burst of updates costs one state change and one draw.
+fn coalesce(events: Vec<AppEvent>) -> Vec<AppEvent> {
+    let mut events: Vec<Option<AppEvent>> = events.into_iter().map(Some).collect();
+    let mut seen = Vec::new();
+    for slot in events.iter_mut().rev() {
+        let Some(kind) = slot.as_ref().and_then(AppEvent::state_update) else {
+            continue;
+        };
+        if seen.contains(&kind) {
+            *slot = None;
+        } else {
+            seen.push(kind);
+        }
+    }
+    events.into_iter().flatten().collect()
+}
+
+/// Adds events to a batch holding a resize until none arrive for [`RESIZE_SETTLE`], up to
+/// [`RESIZE_MAX_DELAY`], for [`coalesce`] to keep only the final size.
+fn settle_resizes(batch: &mut Vec<AppEvent>, rx: &mpsc::Receiver<AppEvent>) {
+    if !batch
+        .iter()
+        .any(|event| event.state_update() == Some(StateUpdate::Resize))
+    {
+        return;
+    }
+    let deadline = Instant::now() + RESIZE_MAX_DELAY;
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        match rx.recv_timeout(RESIZE_SETTLE.min(left)) {
+            Ok(event) => {
+                batch.push(event);
+                batch.extend(rx.try_iter());
+            }
+            Err(_) => break,
+        }
+    }
+}
+
+/// One log panel state per tab, showing only the tab's target if it has one. Tabs without a
+/// level of their own use `default`.
+fn log_tab_states(tabs: &[LogTab], default: LevelFilter) -> Vec<TuiWidgetState> {
+    tabs.iter()
+        .map(|tab| {
+            let level = tab.level.unwrap_or(default);
+            match &tab.target {
+                Some(target) => TuiWidgetState::new()
+                    .set_default_display_level(LevelFilter::Off)
+                    .set_level_for_target(target, level),
+                None => TuiWidgetState::new().set_default_display_level(level),
+            }
+        })
+        .collect()
+}
+
+/// Runs `task` on its own thread, reporting a failure to the UI as [`AppEvent::TaskError`]
+/// instead of panicking the thread. The thread logs inside a `task` span, nested in the
+/// caller's span, with `component` set to `name`.
+fn spawn_task<F>(name: &'static str, tx: mpsc::Sender<AppEvent>, task: F)
+where
+    F: FnOnce(mpsc::Sender<AppEvent>) -> anyhow::Result<()> + Send + 'static,
+{
+    let error_tx = tx.clone();
+    let span = tracing::info_span!("task", component = name);
+    thread::spawn(move || {
+        let _entered = span.enter();
+        if let Err(err) = task(tx) {
+            // the send fails too if the UI has already gone away
+            let _ = error_tx.send(AppEvent::TaskError(format!("{name}: {err}")));
+        }
+    });
+}
+
+/// Forwards piped stdin to the console a line at a time until EOF.
+fn stdin_task(tx: mpsc::Sender<AppEvent>) -> anyhow::Result<()> {
+    for line in io::stdin().lock().lines() {
+        tx.send(AppEvent::StdinLine(line?))?;
+    }
+    Ok(())
+}
+
+/// Checks a submitted line, returning the reason it was rejected.
+type Validator = Box<dyn Fn(&str) -> Result<(), String>>;
+
+/// The checks on submitted lines set in `config.toml`.
+fn config_validator(config: &Config) -> Validator {
+    let allow_empty = config.allow_empty_input;
+    let max_length = config.input_max_length;
+    let pattern = config.input_pattern.clone();
+    Box::new(move |line| {
+        if !allow_empty && line.trim().is_empty() {
+            return Err("input is empty".to_string());
+        }
+        if let Some(max_length) = max_length {
+            if line.chars().count() > max_length {
+                return Err(format!("input is longer than {max_length} characters"));
+            }
+        }
+        match &pattern {
+            Some(pattern) if !pattern.is_match(line) => {
+                Err(format!("input does not match {}", pattern.as_str()))
+            }
+            _ => Ok(()),
+        }
+    })
+}
+
+/// Sends [`AppEvent::Tick`] at a fixed rate so time-based state such as toasts can expire
+/// without waiting for input.
+fn tick_task(tx: mpsc::Sender<AppEvent>, rate: Duration) -> anyhow::Result<()> {
+    loop {
+        thread::sleep(rate);
+        tx.send(AppEvent::Tick)?;
+    }
+}
+
+/// A simulated task that sends a counter value to the UI ranging from 0 to 100 every second.
+/// It keeps running after reaching 100 so the UI can pause it or restart it from 0.
+fn progress_task(
+    tx: mpsc::Sender<AppEvent>,
+    control: mpsc::Receiver<ProgressControl>,
+) -> anyhow::Result<()> {
+    let mut progress = 0;
+    let mut paused = false;
+    let mut reported_busy = false;
+    loop {
+        if !paused && progress <= 100 {
+            if progress < 100 {
+                // debug!(target:"progress-task", "Send progress to UI thread. Value: {:?}", progress);
+                tx.send(AppEvent::CounterChanged(PROGRESS_TASK, Some(progress)))?;
+            } else {
+                // info!(target:"progress-task", "Progress task finished");
+                tx.send(AppEvent::CounterChanged(PROGRESS_TASK, None))?;
+            }
+            progress += 1;
+        }
+        let busy = !paused && progress <= 100;
+        if busy != reported_busy {
+            tx.send(if busy {
+                AppEvent::TaskStarted(PROGRESS_TASK)
+            } else {
+                AppEvent::TaskFinished(PROGRESS_TASK)
+            })?;
+            reported_busy = busy;
+        }
+
+        // Wait for the next step, waking up early for control requests
+        match control.recv_timeout(Duration::from_millis(1000)) {
+            Ok(ProgressControl::TogglePause) => paused = !paused,
+            Ok(ProgressControl::Restart) => {
+                paused = false;
+                progress = 0;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+/// Opens `text` in `$VISUAL` or `$EDITOR` (`vi` if neither is set) and returns what was saved,
+/// without the trailing newline editors add.
+fn edit_in_editor(text: &str) -> io::Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // allow arguments, e.g. `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::other("the editor variable is empty"))?;
+    let path = std::env::temp_dir().join(format!(
+        "{}-input-{}.txt",
+        env!("CARGO_CRATE_NAME"),
+        std::process::id()
+    ));
+    // only a file this process created, readable by no one else, so nothing can be planted
+    // at the predictable path
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&path)?;
+    if let Err(err) = file.write_all(text.as_bytes()) {
+        let _ = std::fs::remove_file(&path);
+        return Err(err);
+    }
+    drop(file);
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status();
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path); // a leftover temp file is harmless
+    let status = status?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{program} exited with {status}")));
+    }
+    Ok(edited?.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// A simulated download running alongside [`progress_task`] at its own pace, so that
+/// several gauges show at once. It repeats with a pause in between.
+fn download_task(tx: mpsc::Sender<AppEvent>) -> anyhow::Result<()> {
+    const TASK: TaskId = "download";
+    loop {
+        tx.send(AppEvent::TaskStarted(TASK))?;
+        for progress in (0..100).step_by(5) {
+            tx.send(AppEvent::CounterChanged(TASK, Some(progress)))?;
+            thread::sleep(Duration::from_millis(400));
+        }
+        tx.send(AppEvent::CounterChanged(TASK, None))?;
+        tx.send(AppEvent::TaskFinished(TASK))?;
+        thread::sleep(Duration::from_secs(10));
+    }
+}
+
+/// Readline-style editing keys, checked before the `tui_input` defaults so they behave the
+/// same whichever version of it is in use and whichever modifier the terminal reports for Alt.
+fn edit_request(key: &KeyEvent) -> Option<InputRequest> {
+    let alt = key.modifiers == KeyModifiers::ALT || key.modifiers == KeyModifiers::META;
+    let ctrl = key.modifiers == KeyModifiers::CONTROL;
+    match key.code {
+        KeyCode::Home if key.modifiers.is_empty() => Some(InputRequest::GoToStart),
+        KeyCode::End if key.modifiers.is_empty() => Some(InputRequest::GoToEnd),
+        KeyCode::Char('a') if ctrl => Some(InputRequest::GoToStart),
+        KeyCode::Char('e') if ctrl => Some(InputRequest::GoToEnd),
+        KeyCode::Left if ctrl => Some(InputRequest::GoToPrevWord),
+        KeyCode::Right if ctrl => Some(InputRequest::GoToNextWord),
+        KeyCode::Char('w') if ctrl => Some(InputRequest::DeletePrevWord),
+        KeyCode::Backspace if alt => Some(InputRequest::DeletePrevWord),
+        KeyCode::Char('k') if ctrl => Some(InputRequest::DeleteTillEnd),
+        _ => None,
+    }
+}
+
+/// Deletes everything before the cursor, like readline's `Ctrl+U`.
+fn delete_till_start(input: &mut Input) {
+    let rest: String = input.value().chars().skip(input.cursor()).collect();
+    *input = Input::new(rest).with_cursor(0);
+}
+
+/// Inserts pasted text at the cursor. Line breaks become spaces so that a paste never submits;
+/// multiline mode splits the input at them instead with [`App::paste_lines`].
+fn paste(input: &mut Input, text: &str) {
+    for ch in text.replace("\r\n", "\n").chars() {
+        let ch = if ch == '\n' || ch == '\r' { ' ' } else { ch };
+        input.handle(InputRequest::InsertChar(ch));
+    }
+}
+
+/// Number of console rows a message occupies when wrapped at `width` columns.
+fn wrapped_rows(message: &str, width: usize, cjk: bool) -> usize {
+    if width == 0 {
+        return 1;
+    }
+    // control characters such as tabs take no columns, like in `char_width`
+    if message.bytes().all(|byte| (0x20..0x7f).contains(&byte)) {
+        return message.len().div_ceil(width).max(1);
+    }
+    row_starts(message, width, cjk).len()
+}
+
+/// Char index at which each console row of `message` starts when wrapped at `width` display
+/// columns. A wide glyph that would straddle the edge moves to the next row.
+fn row_starts(message: &str, width: usize, cjk: bool) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut used = 0;
+    for (index, ch) in message.chars().enumerate() {
+        let ch_width = char_width(ch, cjk);
+        if used + ch_width > width.max(1) && used > 0 {
+            starts.push(index);
+            used = 0;
+        }
+        used += ch_width;
+    }
+    starts
+}
+
+/// Terminal columns taken by `ch`. With `cjk`, East Asian ambiguous-width characters count as
+/// two columns like CJK terminals draw them. Control characters take none.
+fn char_width(ch: char, cjk: bool) -> usize {
+    if cjk {
+        ch.width_cjk().unwrap_or(0)
+    } else {
+        ch.width().unwrap_or(0)
+    }
+}
+
+/// A background task that logs a log entry for each log level every second.
+fn background_task() {
+    loop {
+        // error!(target:"background-task", "an error");
+        // warn!(target:"background-task", "a warning");
+        // info!(target:"background-task", "an info");
+        // debug!(target:"background-task", "a debug");
+        // trace!(target:"background-task", "a trace");
+        thread::sleep(Duration::from_millis(1000));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CopyLineEnding, HighlightRule};
+    use ratatui::backend::TestBackend;
+    use ratatui::widgets::{Paragraph, Wrap};
+    use std::time::Instant;
+
+    fn ctrl(ch: char) -> Event {
+        Event::Key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::CONTROL))
+    }
+
+    fn key(code: KeyCode) -> AppEvent {
+        AppEvent::UiEvent(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+    }
+
+    fn key_with(code: KeyCode, modifiers: KeyModifiers) -> AppEvent {
+        AppEvent::UiEvent(Event::Key(KeyEvent::new(code, modifiers)))
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for ch in text.chars() {
+            app.handle_event(key(KeyCode::Char(ch)));
+        }
+    }
+
+    fn test_terminal(width: u16, height: u16) -> Terminal<TestBackend> {
+        Terminal::new(TestBackend::new(width, height)).unwrap()
+    }
+
+    fn rows(buf: &Buffer) -> Vec<String> {
+        rows_within(buf, buf.area)
+    }
+
+    /// Returns the text of each row of `rect`, for `contains` style assertions.
+    fn rows_within(buf: &Buffer, rect: Rect) -> Vec<String> {
+        (rect.top()..rect.bottom())
+            .map(|y| {
+                (rect.left()..rect.right())
+                    .map(|x| buf.get(x, y).symbol())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn input_box_shows_typed_text() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        type_text(&mut app, "hello world");
+        app.draw(&mut terminal).unwrap();
+
+        let buf = terminal.backend().buffer();
+        assert!(rows_within(buf, app.input_rect)
+            .iter()
+            .any(|row| row.contains("hello world")));
+    }
+
+    #[test]
+    fn submitted_line_moves_to_console() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        type_text(&mut app, "ping");
+        app.handle_event(key(KeyCode::Enter));
+        app.draw(&mut terminal).unwrap();
+
+        let buf = terminal.backend().buffer();
+        assert_eq!(app.input.value(), "");
+        assert!(rows_within(buf, app.console_rect)
+            .iter()
+            .any(|row| row.contains("ping")));
+        assert!(!rows_within(buf, app.input_rect)
+            .iter()
+            .any(|row| row.contains("ping")));
+    }
+
+    #[test]
+    fn run_loop_plays_scripted_events() {
+        let (tx, rx) = mpsc::channel();
+        let mut events: Vec<AppEvent> = "hi".chars().map(|ch| key(KeyCode::Char(ch))).collect();
+        events.extend([
+            key(KeyCode::Enter),
+            AppEvent::UiEvent(Event::Resize(60, 15)),
+            AppEvent::Tick,
+        ]);
+        input_thread(ScriptedEvents::new(events), tx).unwrap();
+
+        let mut terminal = test_terminal(60, 15);
+        let mut app = App::new();
+        let reason = app.run(&mut terminal, &rx).unwrap();
+        assert_eq!(reason, Some(ShutdownReason::EventSourcesEnded));
+        assert_eq!(history_entries(&app), ["hi"]);
+        let rows = rows(terminal.backend().buffer());
+        assert!(rows.iter().any(|row| row.contains("> hi")));
+        assert!(rows
+            .iter()
+            .any(|row| row.contains("all event sources ended")));
+    }
+
+    #[test]
+    fn resize_drag_draws_once_it_settles() {
+        let resize = |width| AppEvent::UiEvent(Event::Resize(width, 20));
+        let (tx, rx) = mpsc::channel();
+        // events still arriving while a resize waits join its batch
+        let mut batch = vec![resize(60)];
+        tx.send(resize(61)).unwrap();
+        tx.send(AppEvent::Tick).unwrap();
+        settle_resizes(&mut batch, &rx);
+        assert_eq!(batch.len(), 3);
+
+        for width in 62..80 {
+            tx.send(resize(width)).unwrap();
+        }
+        drop(tx);
+        let mut terminal = test_terminal(80, 20);
+        let mut app = App::new();
+        app.run(&mut terminal, &rx).unwrap();
+        // one frame for the whole drag and one for the end of input
+        assert_eq!(terminal.get_frame().count(), 2);
+    }
+
+    #[test]
+    fn coalesce_keeps_the_newest_update_of_each_kind() {
+        let events = coalesce(vec![
+            AppEvent::UiEvent(Event::Resize(60, 20)),
+            AppEvent::CounterChanged("a", Some(1)),
+            AppEvent::CounterChanged("b", Some(5)),
+            AppEvent::CounterChanged("a", Some(2)),
+            AppEvent::UiEvent(Event::Resize(70, 20)),
+        ]);
+        let kept: Vec<String> = events.iter().map(|event| format!("{event:?}")).collect();
+        assert_eq!(
+            kept,
+            [
+                r#"CounterChanged("b", Some(5))"#,
+                r#"CounterChanged("a", Some(2))"#,
+                "UiEvent(Resize(70, 20))",
+            ]
+        );
+    }
+
+    #[test]
+    fn gauge_shows_counter_percent() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        app.handle_event(AppEvent::CounterChanged(PROGRESS_TASK, Some(42)));
+        app.draw(&mut terminal).unwrap();
+        assert!(rows(terminal.backend().buffer())
+            .iter()
+            .any(|row| row.contains("42%")));
+
+        app.handle_event(AppEvent::CounterChanged(PROGRESS_TASK, None));
+        app.draw(&mut terminal).unwrap();
+        let rows = rows(terminal.backend().buffer());
+        assert!(!rows.iter().any(|row| row.contains("42%")));
+        assert!(rows.iter().any(|row| row.contains("Idle")));
+    }
+
+    #[test]
+    fn each_task_gets_its_own_gauge() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        app.handle_event(AppEvent::CounterChanged(PROGRESS_TASK, Some(42)));
+        app.handle_event(AppEvent::CounterChanged("download", Some(7)));
+        app.draw(&mut terminal).unwrap();
+        let gauges: Vec<_> = rows(terminal.backend().buffer())
+            .into_iter()
+            .filter(|row| row.contains('%'))
+            .collect();
+        assert_eq!(gauges.len(), 2);
+        assert!(gauges[0].contains("download 7%"));
+        assert!(gauges[1].contains("progress 42%"));
+
+        app.handle_event(AppEvent::CounterChanged("download", None));
+        assert_eq!(app.progress_gauges().len(), 1);
+        assert!(!app.displayed_progress.contains_key("download"));
+    }
+
+    #[test]
+    fn idle_timeout_counts_down_then_quits() {
+        let mut app = App::with_config(Config {
+            idle_timeout_secs: Some(60),
+            ..Config::default()
+        });
+        app.handle_event(AppEvent::Tick);
+        assert_eq!(app.idle_countdown(), None);
+
+        app.last_activity -= Duration::from_millis(55_500);
+        assert_eq!(app.idle_countdown().as_deref(), Some(" Quit in 5s "));
+        app.handle_event(key(KeyCode::Char('a')));
+        assert_eq!(app.idle_countdown(), None);
+
+        app.last_activity -= Duration::from_secs(60);
+        app.handle_event(AppEvent::Tick);
+        assert_eq!(app.mode, AppMode::Quit);
+    }
+
+    #[test]
+    fn gauge_eases_toward_counter_on_tick() {
+        let mut app = App::new();
+        app.handle_event(AppEvent::CounterChanged(PROGRESS_TASK, Some(40)));
+        assert_eq!(app.displayed_progress[PROGRESS_TASK], 0.0);
+        app.handle_event(AppEvent::Tick);
+        assert_eq!(app.displayed_progress[PROGRESS_TASK], 20.0);
+        for _ in 0..20 {
+            app.handle_event(AppEvent::Tick);
+        }
+        assert_eq!(app.displayed_progress[PROGRESS_TASK], 40.0);
+
+        // restarting jumps back instead of sliding
+        app.handle_event(AppEvent::CounterChanged(PROGRESS_TASK, Some(0)));
+        assert_eq!(app.displayed_progress[PROGRESS_TASK], 0.0);
+
+        app.config.progress_smoothing = 0.0;
+        app.handle_event(AppEvent::CounterChanged(PROGRESS_TASK, Some(10)));
+        assert_eq!(app.displayed_progress[PROGRESS_TASK], 10.0);
+    }
+
+    #[test]
+    fn resize_relayouts_panels() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        type_text(&mut app, "abc");
+        app.draw(&mut terminal).unwrap();
+        let before = app.input_rect;
+
+        terminal.backend_mut().resize(120, 40);
+        app.handle_event(AppEvent::UiEvent(Event::Resize(120, 40)));
+        app.draw(&mut terminal).unwrap();
+
+        assert_eq!(terminal.backend().buffer().area, Rect::new(0, 0, 120, 40));
+        assert!(app.input_rect.width > before.width);
+        assert_eq!(app.input_rect.bottom(), app.status_rect.top());
+        assert!(rows_within(terminal.backend().buffer(), app.input_rect)
+            .iter()
+            .any(|row| row.contains("abc")));
+    }
+
+    #[test]
+    fn small_terminal_shows_size_message() {
+        let mut terminal = test_terminal(30, 8);
+        let mut app = App::new();
+        app.draw(&mut terminal).unwrap();
+        let text = rows(terminal.backend().buffer()).concat();
+        assert!(text.contains("Terminal too small"));
+        assert!(!text.contains("Console"));
+
+        terminal.backend_mut().resize(80, 24);
+        app.draw(&mut terminal).unwrap();
+        let text = rows(terminal.backend().buffer()).concat();
+        assert!(!text.contains("Terminal too small"));
+        assert!(text.contains("Console"));
+    }
+
+    #[test]
+    fn small_terminal_forgets_both_split_panes() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        app.set_focus(FocusMode::Console);
+        app.handle_event(key(KeyCode::Char('s')));
+        app.draw(&mut terminal).unwrap();
+        assert!(app.split.is_some_and(|pane| pane.rect.height > 0));
+
+        terminal.backend_mut().resize(30, 8);
+        app.draw(&mut terminal).unwrap();
+        assert_eq!(app.console_rect, Rect::default());
+        assert_eq!(app.split.map(|pane| pane.rect), Some(Rect::default()));
+    }
+
+    #[test]
+    fn compact_mode_drops_borders() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        app.compact = true;
+        app.push_message("hello".into());
+        type_text(&mut app, "hi");
+        app.draw(&mut terminal).unwrap();
+
+        assert_eq!(app.input_rect.height, 1);
+        let buf = terminal.backend().buffer();
+        let console = app.console_rect;
+        assert_eq!(
+            rows_within(buf, Rect::new(console.x, console.y, 5, 1)),
+            ["hello"]
+        );
+        let prompt_width = app.prompt_width();
+        let input = Rect::new(app.input_rect.x + prompt_width, app.input_rect.y, 2, 1);
+        assert_eq!(rows_within(buf, input), ["hi"]);
+        assert_eq!(terminal.get_cursor().unwrap(), (input.x + 2, input.y));
+    }
+
+    #[test]
+    fn select_all_submit_is_replaced_by_typing() {
+        let mut app = App::new();
+        app.config.submit_behavior = SubmitBehavior::SelectAll;
+        type_text(&mut app, "ping");
+        app.handle_event(key(KeyCode::Enter));
+        assert_eq!(app.input.value(), "ping");
+        app.handle_event(key(KeyCode::Enter));
+        assert_eq!(app.messages.len(), 2);
+
+        type_text(&mut app, "x");
+        assert_eq!(app.input.value(), "x");
+
+        app.config.submit_behavior = SubmitBehavior::Keep;
+        app.handle_event(key(KeyCode::Enter));
+        type_text(&mut app, "y");
+        assert_eq!(app.input.value(), "xy");
+    }
+
+    #[test]
+    fn rejected_input_stays_in_the_input() {
+        let mut app = App::new();
+        app.set_validator(|line| match line.len() {
+            0..=3 => Ok(()),
+            _ => Err("too long".to_string()),
+        });
+        type_text(&mut app, "hello");
+        app.handle_event(key(KeyCode::Enter));
+        assert!(app.messages.is_empty());
+        assert_eq!(app.input.value(), "hello");
+        assert_eq!(app.toast.as_ref().unwrap().0, "too long");
+        assert!(app.input_invalid);
+
+        app.handle_event(key(KeyCode::Backspace));
+        app.handle_event(key(KeyCode::Backspace));
+        assert!(!app.input_invalid);
+        app.handle_event(key(KeyCode::Enter));
+        assert_eq!(app.messages.len(), 1);
+    }
+
+    #[test]
+    fn esc_only_cancels_and_quit_has_a_configurable_binding() {
+        let mut app = console_app(&["hello"]);
+        app.selection_start = Some((0, 0));
+        app.selection_end = Some((0, 4));
+        app.handle_event(key(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Run);
+        assert_eq!(app.selection_range(), None);
+
+        app.handle_ui_event(ctrl('q'));
+        assert_eq!(app.mode, AppMode::Quit);
+
+        let mut config = Config::default();
+        config
+            .keybindings
+            .insert(Action::Quit, "ctrl+g".parse().unwrap());
+        let mut app = App::with_config(config);
+        app.handle_ui_event(ctrl('q'));
+        assert_eq!(app.mode, AppMode::Run);
+        app.handle_ui_event(ctrl('g'));
+        assert_eq!(app.mode, AppMode::Quit);
+    }
+
+    #[test]
+    fn help_lists_configured_bindings() {
+        let mut terminal = test_terminal(80, 24);
+        let mut config = Config::default();
+        config
+            .keybindings
+            .insert(Action::Quit, "ctrl+x".parse().unwrap());
+        let mut app = App::with_config(config);
+        app.handle_event(key(KeyCode::F(1)));
+        app.draw(&mut terminal).unwrap();
+        let text = rows(terminal.backend().buffer()).join("\n");
+        assert!(text.contains("Ctrl+X  Quit"));
+        assert!(!text.contains("Ctrl+Q"));
+
+        app.handle_event(key(KeyCode::Esc));
+        assert!(app.help_scroll.is_none());
+    }
+
+    #[test]
+    fn home_and_end_scroll_a_long_line_into_view() {
+        let mut terminal = test_terminal(50, 12);
+        let mut app = App::new();
+        let url = format!("https://example.com/{}end", "a".repeat(60));
+        paste(&mut app.input, &url);
+        app.draw(&mut terminal).unwrap();
+        let input = app.input_rect;
+        let last_column = input.right() - 2;
+        assert_eq!(terminal.get_cursor().unwrap(), (last_column, input.y + 1));
+        assert!(rows_within(terminal.backend().buffer(), input)[1].contains("aend"));
+
+        for start in [key(KeyCode::Home), AppEvent::UiEvent(ctrl('a'))] {
+            app.handle_event(start);
+            app.draw(&mut terminal).unwrap();
+            assert_eq!(app.scroll, 0);
+            assert_eq!(terminal.get_cursor().unwrap(), (input.x + 3, input.y + 1));
+            assert!(rows_within(terminal.backend().buffer(), input)[1].contains("> https://"));
+
+            app.handle_event(AppEvent::UiEvent(ctrl('e')));
+            app.draw(&mut terminal).unwrap();
+            assert_eq!(terminal.get_cursor().unwrap(), (last_column, input.y + 1));
+        }
+        app.handle_event(key(KeyCode::Home));
+        app.handle_event(key(KeyCode::End));
+        assert_eq!(app.input.cursor(), url.chars().count());
+    }
+
+    #[test]
+    fn f5_cycles_the_log_level() {
+        let mut app = App::new();
+        app.handle_event(key(KeyCode::F(5)));
+        assert_eq!(app.log_level, LevelFilter::Debug);
+        assert_eq!(app.toast.as_ref().unwrap().0, "log level: DEBUG");
+        app.handle_event(key(KeyCode::F(5)));
+        app.handle_event(key(KeyCode::F(5)));
+        assert_eq!(app.log_level, LevelFilter::Error);
+        assert_eq!(app.states.len(), app.config.log_tabs.len());
+    }
+
+    #[test]
+    fn hovering_an_unfocused_panel_hints_its_border() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        app.draw(&mut terminal).unwrap();
+        let moved = |rect: Rect| {
+            AppEvent::UiEvent(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Moved,
+                column: rect.x + 2,
+                row: rect.y + 1,
+                modifiers: KeyModifiers::NONE,
+            }))
+        };
+        let border = |terminal: &Terminal<TestBackend>, rect: Rect| {
+            terminal.backend().buffer().get(rect.x, rect.y).fg
+        };
+
+        app.handle_event(moved(app.console_rect));
+        app.draw(&mut terminal).unwrap();
+        assert_eq!(app.hovered, Some(FocusMode::Console));
+        assert_eq!(border(&terminal, app.console_rect), app.theme.hover_border);
+        // the focused input keeps its own highlight, hovered or not
+        app.handle_event(moved(app.input_rect));
+        app.draw(&mut terminal).unwrap();
+        assert_eq!(border(&terminal, app.input_rect), app.theme.focused_border);
+        assert_eq!(
+            border(&terminal, app.console_rect),
+            app.theme.unfocused_border
+        );
+
+        app.handle_event(moved(app.status_rect));
+        assert_eq!(app.hovered, None);
+    }
+
+    #[test]
+    fn console_only_mouse_is_released_outside_the_console() {
+        let mut app = console_app(&["hello"]);
+        app.input_rect = Rect::new(0, 5, 12, 3);
+        let moved = |row| {
+            AppEvent::UiEvent(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Moved,
+                column: 2,
+                row,
+                modifiers: KeyModifiers::NONE,
+            }))
+        };
+        app.handle_event(moved(6));
+        assert!(!app.mouse_released);
+        assert_eq!(app.hovered, Some(FocusMode::Input));
+
+        app.handle_event(key_with(KeyCode::F(10), KeyModifiers::SHIFT));
+        assert!(app.mouse_console_only);
+        app.handle_event(moved(1));
+        assert!(!app.mouse_released);
+        app.handle_event(moved(6));
+        assert!(app.mouse_released);
+        assert_eq!(app.hovered, None);
+
+        // any key takes the mouse back, and F10 turns capture off for good
+        app.handle_event(key(KeyCode::Down));
+        assert!(!app.mouse_released);
+        app.handle_event(moved(6));
+        app.handle_event(key(KeyCode::F(10)));
+        assert!(!app.mouse_captured);
+        assert!(!app.mouse_released);
+    }
+
+    #[test]
+    fn alt_u_clears_the_whole_input() {
+        let mut terminal = test_terminal(50, 12);
+        let mut app = App::new();
+        type_text(&mut app, &"a message too long for the box ".repeat(3));
+        app.handle_event(key(KeyCode::Left));
+        app.draw(&mut terminal).unwrap();
+        assert!(app.scroll > 0);
+
+        app.handle_event(AppEvent::UiEvent(Event::Key(KeyEvent::new(
+            KeyCode::Char('u'),
+            KeyModifiers::ALT,
+        ))));
+        assert_eq!(app.input.value(), "");
+        assert_eq!(app.scroll, 0);
+        assert_eq!(app.mode, AppMode::Run);
+        assert!(app.messages.is_empty());
+    }
+
+    #[test]
+    fn enter_on_empty_input_appends_nothing_unless_enabled() {
+        let mut app = App::new();
+        app.handle_event(key(KeyCode::Enter));
+        assert!(app.messages.is_empty());
+        assert_eq!(app.focus_mode, FocusMode::Input);
+
+        app.config.empty_enter = EmptyEnter::FocusConsole;
+        app.handle_event(key(KeyCode::Enter));
+        assert!(app.messages.is_empty());
+        assert_eq!(app.focus_mode, FocusMode::Console);
+
+        let mut app = App::with_config(Config {
+            submit_empty: true,
+            ..Config::default()
+        });
+        app.handle_event(key(KeyCode::Enter));
+        assert_eq!(app.messages.len(), 1);
+    }
+
+    #[test]
+    fn dot_repeats_the_last_command_confirming_destructive_ones() {
+        let mut app = App::with_config(Config {
+            confirm_clear: false,
+            ..Config::default()
+        });
+        let run = |app: &mut App, line: &str| {
+            app.set_focus(FocusMode::Input);
+            type_text(app, line);
+            app.handle_event(key(KeyCode::Enter));
+            app.set_focus(FocusMode::Console);
+        };
+        run(&mut app, ":echo hi");
+        app.handle_event(key(KeyCode::Char('.')));
+        assert_eq!(app.messages.len(), 2);
+
+        // a failed command is not the one repeated
+        run(&mut app, ":theme nope");
+        app.handle_event(key(KeyCode::Char('.')));
+        assert_eq!(app.messages.len(), 3);
+
+        run(&mut app, ":clear");
+        app.push_message("kept".into());
+        app.handle_event(key(KeyCode::Char('.')));
+        assert_eq!(app.confirm.as_ref().unwrap().prompt, "Repeat :clear?");
+        app.handle_event(key(KeyCode::Char('n')));
+        assert_eq!(app.messages.len(), 1);
+        app.handle_event(key(KeyCode::Char('.')));
+        app.handle_event(key(KeyCode::Char('y')));
+        assert!(app.messages.is_empty());
+    }
+
+    #[test]
+    fn clear_waits_for_confirmation() {
+        let mut app = console_app(&["one", "two"]);
+        app.set_focus(FocusMode::Console);
+        app.handle_ui_event(ctrl('l'));
+        assert_eq!(app.confirm.as_ref().unwrap().prompt, "Clear console?");
+        assert_eq!(app.messages.len(), 2);
+        app.handle_event(key(KeyCode::Char('n')));
+        assert_eq!(app.messages.len(), 2);
+
+        app.handle_ui_event(ctrl('l'));
+        app.handle_event(key(KeyCode::Char('y')));
+        assert!(app.messages.is_empty());
+
+        app.config.confirm_clear = false;
+        app.push_message("three".into());
+        app.handle_ui_event(ctrl('l'));
+        assert!(app.confirm.is_none());
+        assert!(app.messages.is_empty());
+    }
+
+    #[test]
+    fn prompt_template_fills_in_known_tokens() {
+        let mut app = App::with_config(Config {
+            input_prompt: "[{level}|{tab}|{nope}|{messages}> {".to_string(),
+            ..Config::default()
+        });
+        assert_eq!(app.prompt(), app.config.input_prompt);
+
+        app.config.input_prompt_template = true;
+        assert_eq!(app.prompt(), "[Info|All|{nope}|0> {");
+        app.handle_event(key(KeyCode::F(5)));
+        app.push_message("hello".into());
+        assert_eq!(app.prompt(), "[Debug|All|{nope}|1> {");
+        assert_eq!(app.prompt_width(), 22);
+    }
+
+    #[test]
+    fn ctrl_x_ctrl_e_requests_the_editor() {
+        let ctrl = |ch| {
+            AppEvent::UiEvent(Event::Key(KeyEvent::new(
+                KeyCode::Char(ch),
+                KeyModifiers::CONTROL,
+            )))
+        };
+        let mut app = App::new();
+        app.handle_event(ctrl('x'));
+        type_text(&mut app, "e");
+        assert!(!app.edit_externally);
+        assert_eq!(app.input.value(), "e");
+
+        app.handle_event(ctrl('x'));
+        app.handle_event(ctrl('e'));
+        assert!(app.edit_externally);
+
+        app.set_input_text("one\ntwo\n");
+        assert_eq!(app.input.value(), "one two");
+        app.multiline = true;
+        app.set_input_text("one\ntwo");
+        assert_eq!(app.input_lines, ["one"]);
+        assert_eq!(app.input.value(), "two");
+    }
+
+    #[test]
+    fn multiline_paste_splits_lines_and_only_the_current_line_scrolls() {
+        let mut app = App::new();
+        app.multiline = true;
+        type_text(&mut app, "ab");
+        app.handle_event(key(KeyCode::Left));
+        app.handle_event(AppEvent::UiEvent(Event::Paste("1\r\n2\n3".to_string())));
+        assert_eq!(app.input_lines, ["a1", "2"]);
+        assert_eq!(app.input.value(), "3b");
+        assert_eq!(app.input.cursor(), 1);
+
+        app.input_lines = vec!["finished line".to_string()];
+        app.input = Input::new("x".repeat(40));
+        let area = Rect::new(0, 0, 20, 4);
+        let mut buf = Buffer::empty(area);
+        ui::render_input(&mut app, area, &mut buf);
+        assert!(app.scroll > 0);
+        assert!(rows_within(&buf, Rect::new(1, 1, 18, 1))[0].contains("finished line"));
+    }
+
+    #[test]
+    fn plain_terminals_turn_down_viewport_and_editor_requests() {
+        let mut terminal = test_terminal(40, 12);
+        assert!(matches!(
+            terminal.toggle_inline(5),
+            Err(AppError::Unsupported(_))
+        ));
+        let mut app = App::new();
+        type_text(&mut app, "draft");
+        app.edit_input_externally(&mut terminal).unwrap();
+        assert_eq!(app.input.value(), "draft");
+        assert!(app.toast.as_ref().unwrap().0.contains("not supported"));
+    }
+
+    #[test]
+    fn selection_clears_only_after_a_successful_copy() {
+        let mut app = console_app(&["hello"]);
+        let select = |app: &mut App| {
+            app.selection_start = Some((0, 0));
+            app.selection_end = Some((0, 4));
+        };
+        select(&mut app);
+        // without a clipboard worker nothing is sent, so nothing waits to be cleared
+        app.copy_selection(Selection::Clipboard);
+        assert!(!app.clear_selection_on_copy);
+
+        app.clear_selection_on_copy = true;
+        app.handle_event(AppEvent::CopyResult(Err("no clipboard".to_string())));
+        assert!(app.selection_range().is_some());
+
+        app.clear_selection_on_copy = true;
+        app.handle_event(AppEvent::CopyResult(Ok(5)));
+        assert!(app.selection_range().is_none());
+
+        // a later copy result, e.g. from copying the whole console, leaves a new selection
+        select(&mut app);
+        app.handle_event(AppEvent::CopyResult(Ok(5)));
+        assert!(app.selection_range().is_some());
+    }
+
+    #[test]
+    fn builder_applies_settings_and_caps_initial_messages() {
+        let app = AppBuilder::default()
+            .tick_rate(Duration::from_millis(100))
+            .max_messages(2)
+            .keybindings([(
+                Action::Quit,
+                KeyBinding::new(KeyCode::Char('x'), KeyModifiers::CONTROL),
+            )])
+            .initial_messages(["one", "two", "three"])
+            .build();
+        assert_eq!(app.config.tick_rate_ms, 100);
+        let texts: Vec<&str> = app.messages.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(texts, ["two", "three"]);
+        assert!(app
+            .keybindings
+            .iter()
+            .any(|(action, key)| *action == Action::Quit && key.to_string() == "Ctrl+X"));
+    }
+
+    #[test]
+    fn initial_focus_applies_to_the_first_frame() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = AppBuilder::default()
+            .initial_focus(FocusMode::Console)
+            .build();
+        app.draw(&mut terminal).unwrap();
+        assert!(rows_within(terminal.backend().buffer(), app.status_rect)[0].contains(" Console "));
+        assert_eq!(
+            terminal
+                .backend()
+                .buffer()
+                .get(app.console_rect.x, app.console_rect.y)
+                .fg,
+            app.theme.focused_border
+        );
+    }
+
+    fn submit(app: &mut App, line: &str) {
+        type_text(app, line);
+        app.handle_event(key(KeyCode::Enter));
+    }
+
+    /// Every history entry, oldest first.
+    fn history_entries(app: &App) -> Vec<&str> {
+        let mut entries = vec![];
+        let mut before = usize::MAX;
+        while let Some((index, entry)) = app.history.search("", before) {
+            entries.push(entry);
+            before = index;
+        }
+        entries.reverse();
+        entries
+    }
+
+    #[test]
+    fn history_skips_consecutive_duplicates() {
+        let mut app = App::new();
+        for line in ["ls", "ls", "pwd", "ls"] {
+            submit(&mut app, line);
+        }
+        assert_eq!(history_entries(&app), ["ls", "pwd", "ls"]);
+        assert_eq!(app.messages.len(), 4);
+
+        let mut app = App::with_config(Config {
+            history_ignore_dups: false,
+            ..Config::default()
+        });
+        submit(&mut app, "ls");
+        submit(&mut app, "ls");
+        assert_eq!(history_entries(&app), ["ls", "ls"]);
+    }
+
+    #[test]
+    fn history_skips_lines_starting_with_a_space() {
+        let mut app = App::with_config(Config {
+            history_ignore_space: true,
+            ..Config::default()
+        });
+        submit(&mut app, " secret");
+        submit(&mut app, "public");
+        assert_eq!(history_entries(&app), ["public"]);
+        assert_eq!(app.messages.len(), 2);
+
+        let mut app = App::new();
+        submit(&mut app, " kept");
+        assert_eq!(history_entries(&app), [" kept"]);
+    }
+
+    #[test]
+    fn split_console_pins_the_top_pane() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        app.set_focus(FocusMode::Console);
+        for i in 0..40 {
+            app.push_message(format!("line {i}").into());
+        }
+        app.draw(&mut terminal).unwrap();
+        app.handle_event(key(KeyCode::Home));
+        app.set_console_scroll(0);
+        app.handle_event(key(KeyCode::Char('s')));
+        for i in 40..60 {
+            app.push_message(format!("line {i}").into());
+        }
+        app.draw(&mut terminal).unwrap();
+
+        let pinned = app.split.unwrap();
+        assert!(pinned.top);
+        assert_eq!(pinned.scroll, 0);
+        assert_eq!(app.console_scroll, app.max_console_scroll());
+        let text = rows(terminal.backend().buffer()).join("\n");
+        assert!(text.contains("line 0 "));
+        assert!(text.contains("line 59"));
+
+        // Tab moves to the top pane, where scrolling leaves the bottom one alone
+        app.handle_event(key(KeyCode::Tab));
+        app.handle_event(key(KeyCode::Down));
+        assert_eq!(app.console_scroll, 1);
+        assert!(app.split.is_some_and(|pane| pane.follow));
+        app.handle_event(key(KeyCode::Tab));
+        assert_eq!(app.focus_mode, FocusMode::Input);
+        assert!(app.follow);
+    }
+
+    #[test]
+    fn click_in_input_moves_cursor() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        type_text(&mut app, "hello world");
+        app.draw(&mut terminal).unwrap();
+        let row = app.input_rect.y + 1;
+        let text_x = app.input_rect.x + 1 + app.prompt_width();
+
+        let click = |column| {
+            AppEvent::UiEvent(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            }))
+        };
+        app.handle_event(click(text_x + 4));
+        assert_eq!(app.input.cursor(), 4);
+        app.handle_event(click(text_x));
+        assert_eq!(app.input.cursor(), 0);
+        app.handle_event(click(text_x + 40));
+        assert_eq!(app.input.cursor(), 11);
+    }
+
+    #[test]
+    fn focus_switch_redraws_border_colors() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        app.draw(&mut terminal).unwrap();
+        assert!(!app.needs_draw());
+        let theme = app.theme.clone();
+        let border_fg = |terminal: &Terminal<TestBackend>, rect: Rect| {
+            terminal.backend().buffer().get(rect.x, rect.y).fg
+        };
+        assert_eq!(border_fg(&terminal, app.input_rect), theme.focused_border);
+        assert_eq!(
+            border_fg(&terminal, app.console_rect),
+            theme.unfocused_border
+        );
+
+        let click = AppEvent::UiEvent(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: app.console_rect.x + 2,
+            row: app.console_rect.y + 2,
+            modifiers: KeyModifiers::NONE,
+        }));
+        app.handle_event(click);
+        assert_eq!(app.focus_mode, FocusMode::Console);
+        assert!(app.needs_draw());
+        app.draw(&mut terminal).unwrap();
+        assert_eq!(border_fg(&terminal, app.console_rect), theme.focused_border);
+        assert_eq!(border_fg(&terminal, app.input_rect), theme.unfocused_border);
+
+        // the focus change alone is enough, even if nothing else marked the app dirty
+        app.dirty = false;
+        app.set_focus(FocusMode::Input);
+        assert!(app.needs_draw());
+    }
+
+    #[test]
+    fn task_error_shows_in_footer() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        app.status_message = Some("ready".to_string());
+        app.handle_event(AppEvent::TaskError("boom".to_string()));
+        app.draw(&mut terminal).unwrap();
+
+        let buf = terminal.backend().buffer();
+        assert!(rows_within(buf, app.status_rect)[0].contains("ready"));
+        assert!(rows(buf)[23].starts_with("Task failed: boom"));
+
+        // expired toasts leave the footer empty on the next tick
+        app.toast.as_mut().unwrap().1 = Instant::now();
+        app.handle_event(AppEvent::Tick);
+        app.draw(&mut terminal).unwrap();
+        assert_eq!(rows(terminal.backend().buffer())[23].trim(), "");
+    }
+
+    #[test]
+    fn rect_contains_includes_top_left_edge() {
+        let app = App::new();
+        let rect = Rect::new(10, 5, 4, 3);
+        assert!(app.rect_contains(rect, 5, 10));
+        assert!(app.rect_contains(rect, 5, 13));
+        assert!(app.rect_contains(rect, 7, 10));
+        assert!(app.rect_contains(rect, 7, 13));
+    }
+
+    #[test]
+    fn rect_contains_excludes_far_edge() {
+        let app = App::new();
+        let rect = Rect::new(10, 5, 4, 3);
+        assert!(!app.rect_contains(rect, 5, 14)); // x + width
+        assert!(!app.rect_contains(rect, 8, 10)); // y + height
+        assert!(!app.rect_contains(rect, 8, 14));
+        assert!(!app.rect_contains(rect, 4, 10));
+        assert!(!app.rect_contains(rect, 5, 9));
+    }
+
+    #[test]
+    fn rect_contains_nothing_in_empty_rect() {
+        let app = App::new();
+        assert!(!app.rect_contains(Rect::new(10, 5, 0, 0), 5, 10));
+        assert!(!app.rect_contains(Rect::new(10, 5, 0, 3), 6, 10));
+        assert!(!app.rect_contains(Rect::new(10, 5, 4, 0), 5, 11));
+        assert!(!app.rect_contains(Rect::default(), 0, 0));
+    }
+
+    #[test]
+    fn ctrl_c_copies_a_console_selection_and_otherwise_asks_to_quit() {
+        let mut app = console_app(&["hello"]);
+        app.set_focus(FocusMode::Console);
+        app.selection_start = Some((0, 0));
+        app.selection_end = Some((0, 4));
+        app.handle_ui_event(ctrl('c'));
+        // the copy was attempted; without a clipboard worker it can only fail
+        assert!(app.toast.as_ref().unwrap().0.starts_with("Copy failed"));
+        assert!(app.confirm.is_none());
+        assert_eq!(app.mode, AppMode::Run);
+
+        // a selection doesn't count while the input is focused
+        app.set_focus(FocusMode::Input);
+        app.handle_ui_event(ctrl('c'));
+        assert_eq!(app.confirm.as_ref().unwrap().prompt, "Quit?");
+        app.handle_ui_event(ctrl('c'));
+        assert_eq!(app.mode, AppMode::Quit);
+
+        let mut app = console_app(&["hello"]);
+        app.set_focus(FocusMode::Console);
+        app.handle_ui_event(ctrl('c'));
+        assert_eq!(app.confirm.as_ref().unwrap().prompt, "Quit?");
+
+        let mut app = App::with_config(Config {
+            ctrl_c_quits: false,
+            bell: BellStyle::Visual,
+            ..Config::default()
+        });
+        app.handle_ui_event(ctrl('c'));
+        assert!(app.confirm.is_none());
+        assert!(app.flash);
+    }
+
+    #[test]
+    fn ctrl_l_clears_console() {
+        let mut app = App::new();
+        app.input = Input::new("draft".to_string());
+        app.messages = VecDeque::from(["first".into(), "second".into()]);
+        app.selection_start = Some((0, 1));
+        app.selection_end = Some((1, 2));
+        app.console_scroll = 1;
+
+        app.handle_ui_event(ctrl('l'));
+        app.handle_event(key(KeyCode::Char('y')));
+
+        assert!(app.messages.is_empty());
+        assert_eq!(app.selection_start, None);
+        assert_eq!(app.selection_end, None);
+        assert_eq!(app.console_scroll, 0);
+        assert_eq!(app.input.value(), "draft");
+        assert_eq!(app.focus_mode, FocusMode::Input);
+
+        let area = Rect::new(0, 0, 80, 20);
+        let mut buf = Buffer::empty(area);
+        (&mut app).render(area, &mut buf);
+        let inner = app.console_inner();
+        for y in inner.top()..inner.bottom() {
+            for x in inner.left()..inner.right() {
+                assert_eq!(buf.get(x, y).symbol(), " ");
+            }
+        }
+    }
+
+    fn three_line_selection(ending: CopyLineEnding) -> Option<String> {
+        let mut app = App::new();
+        app.config.copy_line_ending = ending;
+        app.messages =
+            VecDeque::from(["first line".into(), "second\r".into(), "third line".into()]);
+        // from "line" on the first message to "third" on the last
+        app.selection_start = Some((0, 6));
+        app.selection_end = Some((2, 4));
+        app.selected_text()
+    }
+
+    /// Runs `script` from a temporary file named after `name`.
+    fn run_script_text(app: &mut App, name: &str, script: &str) -> Result<(), AppError> {
+        let path = std::env::temp_dir().join(format!(
+            "{}-{name}-{}.txt",
+            env!("CARGO_CRATE_NAME"),
+            std::process::id()
+        ));
+        std::fs::write(&path, script).unwrap();
+        let result = app.run_script(&path);
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn scripts_run_commands_in_order_skipping_blanks_and_comments() {
+        let mut app = App::new();
+        let script = "# greeting\n\necho one\n  :echo two  \nlevel debug\n";
+        run_script_text(&mut app, "order", script).unwrap();
+        let texts: Vec<_> = app.messages.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, ["one", "two"]);
+        assert_eq!(app.log_level, LevelFilter::Debug);
+    }
+
+    #[test]
+    fn script_clear_does_not_ask_to_confirm() {
+        let mut app = App::new();
+        assert!(app.config.confirm_clear);
+        run_script_text(&mut app, "clear", "echo a\nclear\necho b").unwrap();
+        let texts: Vec<_> = app.messages.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, ["b"]);
+        assert!(app.confirm.is_none());
+    }
+
+    #[test]
+    fn script_errors_name_the_line_and_stop_there() {
+        let mut app = App::new();
+        let result = run_script_text(&mut app, "error", "echo one\n\n# note\nbogus\necho two");
+        assert!(matches!(result, Err(AppError::Script { line: 4, .. })));
+        assert_eq!(app.messages.len(), 1);
+
+        let result = run_script_text(&mut app, "quit", "echo three\nquit");
+        assert!(matches!(result, Err(AppError::Script { line: 2, .. })));
+        assert_eq!(app.mode, AppMode::Run);
+    }
+
+    #[test]
+    fn run_reports_when_all_event_sources_end() {
+        let mut terminal = test_terminal(80, 24);
+        let (tx, rx) = mpsc::channel();
+        // stands in for the clipboard worker and the endless tasks, which never hang up
+        let _background = tx.clone();
+        let mut app = App::new();
+        app.spawn_source("input", tx.clone(), |tx| {
+            input_thread(ScriptedEvents::new([AppEvent::Tick]), tx)
+        });
+        app.spawn_source("stdin", tx, |_| anyhow::bail!("read failed"));
+        let reason = app.run(&mut terminal, &rx).unwrap();
+        assert_eq!(reason, Some(ShutdownReason::EventSourcesEnded));
+        assert_eq!(app.live_sources, 0);
+        assert_eq!(
+            app.messages.back().unwrap().text,
+            "task failed: stdin: read failed"
+        );
+    }
+
+    #[test]
+    fn start_without_background_tasks_ends_with_its_script() {
+        let mut terminal = test_terminal(80, 24);
+        let app = AppBuilder::default()
+            .background_tasks(false)
+            .history_path(None)
+            .build();
+        let script = "hi"
+            .chars()
+            .map(|ch| key(KeyCode::Char(ch)))
+            .chain([key(KeyCode::Enter)]);
+        let reason = app
+            .start(&mut terminal, ScriptedEvents::new(script))
+            .unwrap();
+        assert_eq!(reason, ShutdownReason::EventSourcesEnded);
+        let rows = rows(terminal.backend().buffer());
+        assert!(rows.iter().any(|row| row.contains("> hi")));
+        assert!(rows
+            .iter()
+            .any(|row| row.contains("all event sources ended")));
+    }
+
+    #[test]
+    fn redraw_handle_forces_a_frame() {
+        let mut terminal = test_terminal(80, 24);
+        let mut app = App::new();
+        app.draw(&mut terminal).unwrap();
+        assert!(!app.needs_draw());
+
+        app.redraw_handle().request().unwrap();
+        let (_, rx) = app.events.take().unwrap();
+        app.handle_event(rx.try_recv().unwrap());
+        assert!(app.needs_draw());
+    }
+
+    #[test]
+    fn run_reports_quit() {
+        let mut terminal = test_terminal(80, 24);
+        let (tx, rx) = mpsc::channel();
+        tx.send(AppEvent::Quit).unwrap();
+        let reason = App::new().run(&mut terminal, &rx).unwrap();
+        assert_eq!(reason, Some(ShutdownReason::Quit));
+    }
+
+    #[test]
+    fn copy_line_ending_lf_terminates_every_line() {
+        assert_eq!(
+            three_line_selection(CopyLineEnding::Lf).as_deref(),
+            Some("line\nsecond\nthird\n")
+        );
+    }
+
+    #[test]
+    fn copy_line_ending_crlf_terminates_every_line() {
+        assert_eq!(
+            three_line_selection(CopyLineEnding::Crlf).as_deref(),
+            Some("line\r\nsecond\r\nthird\r\n")
+        );
+    }
+
+    #[test]
+    fn copy_line_ending_none_on_last_only_separates() {
+        assert_eq!(
+            three_line_selection(CopyLineEnding::NoneOnLast).as_deref(),
+            Some("line\nsecond\nthird")
+        );
+    }
+
+    /// An app with a 12x5 console, whose text starts right after the border at (1, 1).
+    fn console_app(messages: &[&str]) -> App {
+        let mut app = App::new();
+        app.messages = messages.iter().map(|message| (*message).into()).collect();
+        app.console_rect = Rect::new(0, 0, 12, 5);
+        app
+    }
+
+    #[test]
+    fn click_on_either_half_of_wide_glyph_selects_it() {
+        let app = console_app(&["日本語"]);
+        assert_eq!(app.console_position(1, 1), (0, 0));
+        assert_eq!(app.console_position(1, 2), (0, 0));
+        assert_eq!(app.console_position(1, 3), (0, 1));
+        assert_eq!(app.console_position(1, 4), (0, 1));
+        assert_eq!(app.console_position(1, 6), (0, 2));
+        // past the end of the text
+        assert_eq!(app.console_position(1, 8), (0, 4));
+    }
+
+    #[test]
+    fn click_maps_columns_around_emoji() {
+        let app = console_app(&["a😀b"]);
+        assert_eq!(app.console_position(1, 1), (0, 0));
+        assert_eq!(app.console_position(1, 2), (0, 1));
+        assert_eq!(app.console_position(1, 3), (0, 1));
+        assert_eq!(app.console_position(1, 4), (0, 2));
+    }
+
+    #[test]
+    fn wide_glyph_wraps_instead_of_straddling_the_edge() {
+        // 4 ASCII columns leave 6, so the fourth glyph starts the second row
+        let app = console_app(&["abcd日本語漢字"]);
+        assert_eq!(row_starts("abcd日本語漢字", 10, false), vec![0, 7]);
+        assert_eq!(wrapped_rows("abcd日本語漢字", 10, false), 2);
+        assert_eq!(app.console_position(1, 10), (0, 6));
+        assert_eq!(app.console_position(2, 1), (0, 7));
+        assert_eq!(app.console_position(2, 3), (0, 8));
+    }
+
+    #[test]
+    fn wide_glyphs_render_in_two_cells() {
+        let mut app = console_app(&["日本語", "a😀b"]);
+        app.selection_start = Some((1, 1));
+        app.selection_end = Some((1, 1));
+        let inner = app.console_inner();
+        let mut buf = Buffer::empty(inner);
+        ui::render_messages(&app, inner, &mut buf);
+
+        assert_eq!(rows_within(&buf, Rect::new(1, 1, 6, 1)), ["日 本 語 "]);
+        let symbols: Vec<&str> = (1..5).map(|x| buf.get(x, 2).symbol()).collect();
+        assert_eq!(symbols, ["a", "😀", " ", "b"]);
+        let selection = app.theme.selection(false);
+        assert_eq!(buf.get(2, 2).bg, selection.bg.unwrap());
+        assert_eq!(buf.get(3, 2).bg, selection.bg.unwrap());
+        assert_ne!(buf.get(4, 2).bg, selection.bg.unwrap());
+    }
+
+    #[test]
+    fn timestamp_column_is_skipped_by_clicks_and_copy() {
+        // a literal format keeps the column fixed at "ts" plus a space
+        let mut app = console_app(&["hello"]);
+        app.config.timestamp_format = Some("ts".to_string());
+        let inner = app.console_inner();
+        let mut buf = Buffer::empty(inner);
+        ui::render_messages(&app, inner, &mut buf);
+        assert_eq!(rows_within(&buf, Rect::new(1, 1, 8, 1)), ["ts hello"]);
+
+        assert_eq!(app.console_position(1, 4), (0, 0));
+        app.selection_start = Some((0, 0));
+        app.selection_end = Some((0, 4));
+        assert_eq!(app.selected_text().as_deref(), Some("hello"));
+        app.config.copy_timestamps = true;
+        assert_eq!(app.selected_text().as_deref(), Some("ts hello"));
+    }
+
+    #[test]
+    fn input_prefix_marks_submitted_lines_only() {
+        let mut app = console_app(&["hello"]);
+        app.messages.push_back(ConsoleLine::input("ls"));
+        let inner = app.console_inner();
+        let mut buf = Buffer::empty(inner);
+        ui::render_messages(&app, inner, &mut buf);
+        assert_eq!(
+            rows_within(&buf, Rect::new(1, 1, 7, 2)),
+            ["hello  ", "> ls   "]
+        );
+
+        // the prefix counts towards the wrap width of submitted lines only
+        app.messages.push_back("0123456789".into());
+        app.messages.push_back(ConsoleLine::input("0123456789"));
+        app.console_rect.height = 7;
+        let inner = app.console_inner();
+        let mut buf = Buffer::empty(inner);
+        ui::render_messages(&app, inner, &mut buf);
+        assert_eq!(
+            rows_within(&buf, Rect::new(1, 3, 10, 3)),
+            ["0123456789", "> 01234567", "  89      "]
+        );
+
+        assert_eq!(app.console_position(2, 3), (1, 0));
+        assert_eq!(app.console_position(2, 1), (1, 0));
+        assert_eq!(app.console_position(5, 3), (3, 8));
+        app.selection_start = Some((0, 0));
+        app.selection_end = Some((1, 1));
+        assert_eq!(app.selected_text().as_deref(), Some("hello\nls"));
+        app.config.copy_input_prefix = true;
+        assert_eq!(app.selected_text().as_deref(), Some("hello\n> ls"));
+    }
+
+    #[test]
+    fn highlight_rules_restyle_matching_lines_under_the_selection() {
+        let rule = |pattern: &str| HighlightRule {
+            pattern: pattern.to_string(),
+            fg: Some(Color::Red),
+            bg: None,
+            bold: false,
+        };
+        let mut app = App::with_config(Config {
+            highlights: vec![rule("ERROR"), rule("(unclosed")],
+            ..Config::default()
+        });
+        assert_eq!(app.highlights.len(), 1);
+        assert!(app.toast.is_some());
+
+        app.messages = ["ok", "an ERROR"].map(Into::into).into();
+        app.console_rect = Rect::new(0, 0, 12, 5);
+        app.selection_start = Some((1, 0));
+        app.selection_end = Some((1, 0));
+        let inner = app.console_inner();
+        let mut buf = Buffer::empty(inner);
+        ui::render_messages(&app, inner, &mut buf);
+        assert_eq!(buf.get(1, 1).fg, app.theme.console_text);
+        assert_eq!(buf.get(2, 2).fg, Color::Red);
+        // the selection wins over the rule
+        let selection = app.theme.selection(false);
+        assert_eq!(buf.get(1, 2).fg, selection.fg.unwrap());
+    }
+
+    #[test]
+    fn j_and_k_move_the_console_cursor_into_view() {
+        let mut app = console_app(&["one", "two", "three", "four", "five", "six"]);
+        app.focus_mode = FocusMode::Console;
+        app.handle_event(key(KeyCode::Char('k')));
+        assert_eq!(app.console_cursor, Some(0));
+        for _ in 0..4 {
+            app.handle_event(key(KeyCode::Char('j')));
+        }
+        // three rows fit, so the fifth message scrolls the first two out
+        assert_eq!(app.console_cursor, Some(4));
+        assert_eq!(app.console_scroll, 2);
+        for _ in 0..10 {
+            app.handle_event(key(KeyCode::Char('j')));
+        }
+        assert_eq!(app.console_cursor, Some(5));
+        app.handle_event(key(KeyCode::Char('k')));
+        app.handle_event(key(KeyCode::Char('k')));
+        app.handle_event(key(KeyCode::Char('k')));
+        assert_eq!(app.console_scroll, 2);
+        app.handle_event(key(KeyCode::Char('k')));
+        assert_eq!(app.console_scroll, 1);
+    }
+
+    #[test]
+    fn visual_mode_selects_with_the_keyboard() {
+        let mut app = console_app(&["hello", "hi"]);
+        app.focus_mode = FocusMode::Console;
+        app.handle_event(key(KeyCode::Char('v')));
+        app.handle_event(key(KeyCode::Right));
+        app.handle_event(key(KeyCode::Char('l')));
+        assert_eq!(app.selected_text().as_deref(), Some("hel"));
+        // down clamps to the shorter line, left wraps back up
+        app.handle_event(key(KeyCode::Down));
+        assert_eq!(app.visual_cursor, Some((1, 1)));
+        app.handle_event(key(KeyCode::Left));
+        app.handle_event(key(KeyCode::Left));
+        assert_eq!(app.visual_cursor, Some((0, 4)));
+
+        let inner = app.console_inner();
+        let mut buf = Buffer::empty(inner);
+        ui::render_messages(&app, inner, &mut buf);
+        assert!(buf.get(5, 1).modifier.contains(Modifier::REVERSED));
+        assert!(!buf.get(4, 1).modifier.contains(Modifier::REVERSED));
+
+        app.handle_event(key(KeyCode::Esc));
+        assert_eq!(app.visual_cursor, None);
+        assert_eq!(app.selection_range(), None);
+    }
+
+    #[test]
+    fn console_updates_rewrite_the_last_line_only_when_editable() {
+        let update = |replace_last, text: &str| AppEvent::ConsoleUpdate {
+            replace_last,
+            text: text.to_string(),
+        };
+        let mut app = console_app(&["start"]);
+        app.handle_event(update(true, "10%"));
+        assert_eq!(app.messages.len(), 2);
+
+        app.config.console_mode = ConsoleMode::Editable;
+        app.selection_start = Some((0, 0));
+        app.selection_end = Some((1, 2));
+        app.handle_event(update(true, "50%"));
+        app.handle_event(update(true, "100%"));
+        let texts: Vec<_> = app.messages.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, ["start", "100%"]);
+        // the selection reached the rewritten line
+        assert_eq!(app.selection_range(), None);
+
+        app.handle_event(update(false, "done"));
+        assert_eq!(app.messages.len(), 3);
+    }
+
+    #[test]
+    fn double_and_triple_clicks_select_the_word_and_the_line() {
+        let mut app = console_app(&["ab cd", "next"]);
+        let click = |row, column| {
+            AppEvent::UiEvent(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            }))
+        };
+        app.handle_event(click(1, 5));
+        assert_eq!(app.selected_text().as_deref(), Some("d"));
+        app.handle_event(click(1, 5));
+        assert_eq!(app.selected_text().as_deref(), Some("cd"));
+        app.handle_event(click(1, 5));
+        assert_eq!(app.selected_text().as_deref(), Some("ab cd"));
+        // a fourth click starts over
+        app.handle_event(click(1, 5));
+        assert_eq!(app.selected_text().as_deref(), Some("d"));
+
+        // clicks on different cells or too far apart are single clicks
+        app.handle_event(click(2, 1));
+        assert_eq!(app.selected_text().as_deref(), Some("n"));
+        app.last_click = app
+            .last_click
+            .map(|(at, cell, count)| (at - MULTI_CLICK_INTERVAL * 2, cell, count));
+        app.handle_event(click(2, 1));
+        assert_eq!(app.selected_text().as_deref(), Some("n"));
+    }
+
+    #[test]
+    fn mirrored_log_records_reach_the_console() {
+        use tracing_subscriber::prelude::*;
+
+        let (tx, rx) = mpsc::channel();
+        let subscriber = tracing_subscriber::registry().with(logging::ConsoleLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!("before mirroring");
+            logging::mirror_to_console(tx, LevelFilter::Warn);
+            tracing::error!(target: "App", code = 3, "disk full");
+            tracing::info!("too verbose");
+            logging::stop_mirroring();
+        });
+
+        let mut app = App::new();
+        rx.try_iter().for_each(|event| app.handle_event(event));
+        let lines: Vec<_> = app
+            .messages
+            .iter()
+            .map(|line| (line.text.as_str(), line.level))
+            .collect();
+        assert_eq!(lines, [("ERROR App: disk full code=3", LineLevel::Error)]);
+    }
+
+    #[test]
+    fn shift_click_extends_the_selection() {
+        let mut app = console_app(&["hello", "second"]);
+        let click = |row, column, modifiers| {
+            AppEvent::UiEvent(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                modifiers,
+            }))
+        };
+        let release = || {
+            AppEvent::UiEvent(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Up(MouseButton::Left),
+                column: 0,
+                row: 0,
+                modifiers: KeyModifiers::NONE,
+            }))
+        };
+        app.handle_event(click(1, 3, KeyModifiers::NONE));
+        app.handle_event(release());
+        app.handle_event(click(2, 4, KeyModifiers::SHIFT));
+        assert_eq!(app.selection_start, Some((0, 2)));
+        assert_eq!(app.selection_end, Some((1, 3)));
+
+        // extending again moves the same end, and a plain click starts over
+        app.handle_event(release());
+        app.handle_event(click(1, 5, KeyModifiers::SHIFT));
+        assert_eq!(app.selection_start, Some((0, 2)));
+        assert_eq!(app.selection_end, Some((0, 4)));
+        app.handle_event(click(2, 2, KeyModifiers::NONE));
+        assert_eq!(app.selection_start, Some((1, 1)));
+        assert_eq!(app.selection_end, Some((1, 1)));
+    }
+
+    #[test]
+    fn drag_below_console_scrolls_on_tick() {
+        let mut app = console_app(&["0", "1", "2", "3", "4", "5"]);
+        let mouse = |kind, row| {
+            AppEvent::UiEvent(Event::Mouse(MouseEvent {
+                kind,
+                column: 1,
+                row,
+                modifiers: KeyModifiers::NONE,
+            }))
+        };
+        app.handle_event(mouse(MouseEventKind::Down(MouseButton::Left), 1));
+        app.handle_event(mouse(MouseEventKind::Drag(MouseButton::Left), 8));
+        assert_eq!(app.selection_end, Some((2, 0)));
+
+        app.handle_event(AppEvent::Tick);
+        assert_eq!(app.console_scroll, 1);
+        assert_eq!(app.selection_end, Some((3, 0)));
+        // stops at the last message
+        for _ in 0..5 {
+            app.handle_event(AppEvent::Tick);
+        }
+        assert_eq!(app.console_scroll, 3);
+        assert_eq!(app.selection_end, Some((5, 0)));
+
+        app.handle_event(mouse(MouseEventKind::Up(MouseButton::Left), 8));
+        app.handle_event(AppEvent::Tick);
+        assert_eq!(app.selection_end, Some((5, 0)));
+        assert!(app.autoscroll.is_none());
+    }
+
+    #[test]
+    fn ambiguous_width_follows_config() {
+        assert_eq!(char_width('±', false), 1);
+        assert_eq!(char_width('±', true), 2);
+        assert_eq!(wrapped_rows("±±±±±±", 10, true), 2);
+    }
+
+    #[test]
+    fn tabs_wrap_the_same_as_they_render() {
+        let line = "a\tb\tc\td\te";
+        assert_eq!(
+            wrapped_rows(line, 4, false),
+            row_starts(line, 4, false).len()
+        );
+        assert_eq!(wrapped_rows(line, 4, false), 2);
+    }
+
+    /// Compares the time to render a frame of 10k console lines as one `Paragraph`, as the
+    /// console did before, with the virtualized renderer. Timings vary too much between
+    /// machines to assert on, so it only reports them; run it with
+    /// `cargo test --release -- --ignored --nocapture console_render_10k`.
+    #[test]
+    #[ignore]
+    fn bench_console_render_10k() {
+        const ITERATIONS: u32 = 20;
+        let mut app = App::new();
+        app.messages = (0..10_000)
+            .map(|i| format!("message number {i}").into())
+            .collect();
+        app.console_rect = Rect::new(0, 0, 120, 40);
+        app.console_scroll = app.max_console_scroll();
+        let inner = app.console_inner();
+
+        let started = Instant::now();
+        for _ in 0..ITERATIONS {
+            let mut buf = Buffer::empty(inner);
+            let lines: Vec<Line> = app
+                .messages
+                .iter()
+                .map(|message| {
+                    Line::from(
+                        message
+                            .text
+                            .chars()
+                            .map(|ch| Span::raw(ch.to_string()))
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .collect();
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .scroll((app.console_scroll as u16, 0))
+                .render(inner, &mut buf);
+        }
+        let paragraph = started.elapsed() / ITERATIONS;
+
+        let started = Instant::now();
+        for _ in 0..ITERATIONS {
+            let mut buf = Buffer::empty(inner);
+            ui::render_messages(&app, inner, &mut buf);
+        }
+        let virtualized = started.elapsed() / ITERATIONS;
+
+        println!("10k lines: paragraph {paragraph:?}/frame, virtualized {virtualized:?}/frame");
+    }
+
+    /// Only the visible window of a long console is laid out, so the tail of 10k lines
+    /// renders without wrapping everything above it.
+    #[test]
+    fn console_render_10k_shows_the_tail() {
+        let mut app = App::new();
+        app.messages = (0..10_000)
+            .map(|i| format!("message number {i}").into())
+            .collect();
+        app.console_rect = Rect::new(0, 0, 120, 40);
+        app.console_scroll = app.max_console_scroll();
+        let inner = app.console_inner();
+
+        let mut buf = Buffer::empty(inner);
+        ui::render_messages(&app, inner, &mut buf);
+        let last_row: String = (inner.left()..inner.right())
+            .map(|x| buf.get(x, inner.bottom() - 1).symbol())
+            .collect();
+        assert!(last_row.contains("message number 9999"), "{last_row:?}");
     }
 }
@@ -0,0 +1,840 @@
+//! Drawing of the [`App`]. The rects remembered while drawing are the ones mouse events are
+//! hit-tested against, so the render functions that lay out a clickable area store it.
+
+use super::{char_width, row_starts};
+use super::{App, FocusMode, MenuItem, SPINNER_FRAMES};
+use crate::action::Action;
+use crate::config::LogPanelPosition;
+use crate::console::LineLevel;
+use color_eyre::Result;
+use ratatui::layout::Flex;
+use ratatui::prelude::*;
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{
+    Block, Borders, Clear, Gauge, List, ListState, Paragraph, Scrollbar, ScrollbarOrientation,
+    ScrollbarState, Tabs, Wrap,
+};
+use std::time::Instant;
+use tui_logger::*;
+use unicode_width::UnicodeWidthStr;
+
+/// Smallest terminal the layout is drawn in; anything smaller gets a message instead.
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 10;
+
+impl App {
+    pub(super) fn draw(&mut self, terminal: &mut Terminal<impl Backend>) -> Result<()> {
+        let started = self.render_stats.is_some().then(Instant::now);
+        terminal.draw(|frame| {
+            frame.render_widget(&mut *self, frame.size());
+            if self.focus_mode == FocusMode::Input
+                && self.command.is_none()
+                && !self.input_rect.is_empty()
+            {
+                let scroll = self.scroll;
+                let border = self.border_width();
+                frame.set_cursor(
+                    // Put cursor past the border, the prompt and the input text
+                    self.input_rect.x
+                        + border
+                        + self.prompt_width()
+                        + (self.input.visual_cursor().max(scroll) - scroll) as u16,
+                    // Move down from the border past any finished lines to the input line
+                    self.input_rect.y + border + self.visible_input_lines().len() as u16,
+                )
+            }
+            if let Some(command) = self
+                .command
+                .as_ref()
+                .filter(|_| !self.status_rect.is_empty())
+            {
+                // Put cursor past the `:` prompt and the command text
+                frame.set_cursor(
+                    self.status_rect.x + command.visual_cursor() as u16 + 1,
+                    self.status_rect.y,
+                )
+            }
+        })?;
+        // the visual bell only lasts for a single frame, so the next tick has to clear it
+        self.dirty = std::mem::take(&mut self.flash);
+        if let (Some(stats), Some(started)) = (self.render_stats.as_mut(), started) {
+            stats.record(started);
+        }
+
+        Ok(())
+    }
+}
+
+impl Widget for &mut App {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            render_too_small(self, area, buf);
+            return;
+        }
+
+        let log_panel = self.config.log_panel;
+        let bottom_percent = if log_panel == LogPanelPosition::Bottom {
+            30
+        } else {
+            0
+        };
+        let [main_area, bottom_area, status_area, footer_area] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Percentage(bottom_percent),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        // as wide as the left column, but never wider than the console next to it
+        let sidebar_percent = if log_panel == LogPanelPosition::Right {
+            self.left_column_percent
+                .min((100 - self.left_column_percent) / 2)
+        } else {
+            0
+        };
+        let [left_col, right_col, sidebar] = Layout::horizontal([
+            Constraint::Percentage(self.left_column_percent),
+            Constraint::Fill(1),
+            Constraint::Percentage(sidebar_percent),
+        ])
+        .areas(main_area);
+        let input_height = self.visible_input_lines().len() as u16 + 1 + 2 * self.border_width();
+        let [console_area, input_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(input_height)])
+                .areas(right_col);
+
+        render_left_column(self, left_col, buf);
+        match log_panel {
+            LogPanelPosition::Right => render_logs(self, sidebar, buf),
+            LogPanelPosition::Bottom => render_logs(self, bottom_area, buf),
+            LogPanelPosition::Left | LogPanelPosition::Hidden => {}
+        }
+        render_console(self, console_area, buf);
+        render_input(self, input_area, buf);
+        render_status(self, status_area, buf);
+        render_footer(self, footer_area, buf);
+        render_overlays(self, area, buf);
+    }
+}
+
+fn render_too_small(app: &mut App, area: Rect, buf: &mut Buffer) {
+    // nothing is on screen to click, type into or put the cursor in
+    app.console_rect = Rect::default();
+    app.scrollbar_rect = Rect::default();
+    app.input_rect = Rect::default();
+    app.status_rect = Rect::default();
+    app.menu_rect = Rect::default();
+    if let Some(pane) = app.split.as_mut() {
+        pane.rect = Rect::default();
+    }
+    let [message_area] = Layout::vertical([Constraint::Length(2)])
+        .flex(Flex::Center)
+        .areas(area);
+    Paragraph::new(format!(
+        "Terminal too small (need {MIN_WIDTH}x{MIN_HEIGHT})"
+    ))
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true })
+    .render(message_area, buf);
+}
+
+/// The configured panels, then the logs when they sit on the left, then a progress gauge per
+/// task.
+pub(super) fn render_left_column(app: &App, area: Rect, buf: &mut Buffer) {
+    let border = app.border_width();
+    let gauges = app.progress_gauges();
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(app.panels.iter().map(|panel| panel.constraint()).chain([
+            Constraint::Min(0),
+            Constraint::Length(gauges.len() as u16 + 2 * border),
+        ]))
+        .split(area);
+    let (panel_rows, rows) = rows.split_at(app.panels.len());
+
+    for (panel, &area) in app.panels.iter().zip(panel_rows) {
+        let block = panel_block(app, panel.title()).style(app.theme.panel());
+        let inner = block.inner(area);
+        block.render(area, buf);
+        panel.render(inner, buf);
+    }
+
+    if app.config.log_panel == LogPanelPosition::Left {
+        render_logs(app, rows[0], buf);
+    }
+
+    let block = panel_block(app, "Progress").style(app.theme.panel());
+    let inner = block.inner(rows[1]);
+    block.render(rows[1], buf);
+    let gauge_rows = Layout::vertical(vec![Constraint::Length(1); gauges.len()]).split(inner);
+    for ((ratio, label), &area) in gauges.into_iter().zip(gauge_rows.iter()) {
+        Gauge::default()
+            .gauge_style(Style::default().fg(app.theme.focused_border))
+            .ratio(ratio)
+            .label(label)
+            .render(area, buf);
+    }
+}
+
+/// The log tabs above the log of the selected tab.
+pub(super) fn render_logs(app: &App, area: Rect, buf: &mut Buffer) {
+    let [tabs_area, log_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+    let tab_names: Vec<&str> = app
+        .config
+        .log_tabs
+        .iter()
+        .map(|tab| tab.name.as_str())
+        .collect();
+    Tabs::new(tab_names)
+        .select(app.selected_tab)
+        .style(app.theme.panel())
+        .highlight_style(app.theme.selection(true))
+        .render(tabs_area, buf);
+    if let Some(state) = app.states.get(app.selected_tab) {
+        TuiLoggerWidget::default()
+            .block(panel_block(app, "Logs (Alt+Left/Right)"))
+            .style(app.theme.panel())
+            .output_level(Some(TuiLoggerLevelOutput::Abbreviated))
+            .output_target(true)
+            .output_file(false)
+            .output_line(false)
+            .state(state)
+            .render(log_area, buf);
+    }
+}
+
+/// One console pane, or two while the console is split, each with its scrollbar.
+pub(super) fn render_console(app: &mut App, area: Rect, buf: &mut Buffer) {
+    match app.split.as_mut() {
+        Some(pane) => {
+            let [top, bottom] = Layout::vertical([Constraint::Fill(1); 2]).areas(area);
+            (app.console_rect, pane.rect) = if pane.top {
+                (bottom, top)
+            } else {
+                (top, bottom)
+            };
+            // draw the inactive pane through the same code by swapping it in
+            app.swap_console_panes();
+            render_console_pane(app, false, buf);
+            app.swap_console_panes();
+        }
+        None => app.console_rect = area,
+    }
+    render_console_pane(app, true, buf);
+}
+
+pub(super) fn render_input(app: &mut App, area: Rect, buf: &mut Buffer) {
+    app.input_rect = area;
+    let input_border = if app.input_invalid {
+        app.theme.console_line(LineLevel::Error)
+    } else {
+        panel_border(app, FocusMode::Input, true)
+    };
+    let input_block = Block::default()
+        .borders(if app.compact {
+            Borders::NONE
+        } else {
+            Borders::ALL
+        })
+        .style(input_border);
+    let input_inner = input_block.inner(area);
+    let prompt_width = app.prompt_width().min(input_inner.width);
+    // the prompt stays put while the text after it scrolls horizontally
+    let input_text_area = Rect {
+        x: input_inner.x + prompt_width,
+        width: input_inner.width - prompt_width,
+        ..input_inner
+    };
+    let width = input_text_area.width.saturating_sub(1); // keep 1 for the cursor
+    app.scroll = app.input.visual_scroll(width as usize);
+    let input_style = Style::default().fg(app.theme.input_text);
+    let value = app.input.value();
+    let current_line = match &app.search {
+        Some(search) if search.matched.is_some() && !search.query.is_empty() => {
+            match value.find(&search.query) {
+                Some(start) => {
+                    let end = start + search.query.len();
+                    Line::from(vec![
+                        Span::raw(&value[..start]),
+                        Span::styled(&value[start..end], app.theme.search_match()),
+                        Span::raw(&value[end..]),
+                    ])
+                }
+                None => Line::from(value),
+            }
+        }
+        _ if app.input_selected => Line::styled(value, app.theme.selection(true)),
+        _ if value.is_empty() && app.input_lines.is_empty() => Line::styled(
+            app.config.input_placeholder.as_str(),
+            input_style.add_modifier(Modifier::DIM),
+        ),
+        _ => Line::from(value),
+    };
+    let finished_lines: Vec<Line> = app
+        .visible_input_lines()
+        .iter()
+        .map(|line| {
+            if app.input_selected {
+                Line::styled(line.as_str(), app.theme.selection(true))
+            } else {
+                Line::from(line.as_str())
+            }
+        })
+        .collect();
+    let title = match &app.search {
+        Some(search) if search.failing => {
+            format!("Input (failing reverse-i-search: '{}')", search.query)
+        }
+        Some(search) => format!("Input (reverse-i-search: '{}')", search.query),
+        None if app.multiline => "Input (multiline)".to_string(),
+        None => "Input".to_string(),
+    };
+    let length = value.chars().count();
+    let counter = match app.config.input_max_length {
+        Some(max_length) if length > max_length => Line::styled(
+            format!("{length}/{max_length}"),
+            app.theme.console_line(LineLevel::Error),
+        ),
+        Some(max_length) => Line::from(format!("{length}/{max_length}")),
+        None => Line::from(length.to_string()),
+    };
+    let input_block = if app.compact {
+        input_block
+    } else {
+        input_block
+            .title(title)
+            .title(Title::from(counter).alignment(Alignment::Right))
+    };
+    input_block.render(area, buf);
+    let prompt_row = input_inner.y + app.visible_input_lines().len() as u16;
+    if prompt_width > 0 && prompt_row < input_inner.bottom() {
+        buf.set_stringn(
+            input_inner.x,
+            prompt_row,
+            app.prompt(),
+            prompt_width as usize,
+            input_style,
+        );
+    }
+    Paragraph::new(finished_lines)
+        .style(input_style)
+        .render(input_text_area, buf);
+    // only the line being edited scrolls, so the finished lines above keep their start
+    if prompt_row < input_inner.bottom() {
+        let current_area = Rect {
+            y: prompt_row,
+            height: 1,
+            ..input_text_area
+        };
+        Paragraph::new(current_line)
+            .style(input_style)
+            .scroll((0, app.scroll as u16))
+            .render(current_area, buf);
+    }
+
+    // mark text scrolled out of view on either side of the line being edited
+    if input_text_area.width > 1 && prompt_row < input_inner.bottom() {
+        if app.scroll > 0 {
+            buf.set_string(input_text_area.x, prompt_row, "<", input_border);
+        }
+        if value.width() > app.scroll + input_text_area.width as usize {
+            buf.set_string(input_text_area.right() - 1, prompt_row, ">", input_border);
+        }
+    }
+}
+
+/// The focus badge, spinner and status message, or the `:` command line.
+pub(super) fn render_status(app: &mut App, area: Rect, buf: &mut Buffer) {
+    app.status_rect = area;
+    let mut status_style = app.theme.panel();
+    if app.flash {
+        status_style = status_style.add_modifier(Modifier::REVERSED);
+    }
+    let status_line = match &app.command {
+        Some(command) => Line::from(format!(":{}", command.value())),
+        None => Line::from(vec![
+            Span::styled(format!(" {} ", app.focus_mode), app.theme.badge()),
+            Span::raw(if app.active_tasks > 0 {
+                format!(" {}", SPINNER_FRAMES[app.spinner_frame])
+            } else {
+                String::new()
+            }),
+            Span::styled(
+                app.idle_countdown().unwrap_or_default(),
+                app.theme.console_line(LineLevel::Error),
+            ),
+            Span::raw(match (app.mouse_captured, app.mouse_released) {
+                (false, _) => " [native selection, F10 to capture mouse] ",
+                (true, true) => " [native selection until a key is pressed] ",
+                (true, false) if app.mouse_console_only => " [mouse in console] ",
+                (true, false) => " ",
+            }),
+            Span::raw(app.status_message.as_deref().unwrap_or_default()),
+        ]),
+    };
+    Paragraph::new(status_line)
+        .style(status_style)
+        .render(area, buf);
+}
+
+/// The latest toast until it expires, on a line of its own so it never hides the status.
+pub(super) fn render_footer(app: &App, area: Rect, buf: &mut Buffer) {
+    let toast = match &app.toast {
+        Some((message, expires, style)) if *expires > Instant::now() => {
+            Line::styled(message.as_str(), *style)
+        }
+        _ => Line::default(),
+    };
+    Paragraph::new(toast)
+        .style(app.theme.panel())
+        .render(area, buf);
+}
+
+/// Popups drawn over the whole frame: render stats, the context menu, help and confirmations.
+fn render_overlays(app: &mut App, area: Rect, buf: &mut Buffer) {
+    if let Some(stats) = &app.render_stats {
+        let overlay = Rect::new(area.right().saturating_sub(24), area.y, 24, 3).intersection(area);
+        Clear.render(overlay, buf);
+        Paragraph::new(format!(
+            "{} fps {:.1?}",
+            stats.frames.len(),
+            stats.last_render
+        ))
+        .block(Block::bordered().title("Render"))
+        .style(app.theme.panel())
+        .alignment(Alignment::Right)
+        .render(overlay, buf);
+    }
+
+    if let Some(menu) = &app.context_menu {
+        let width = MenuItem::ALL
+            .iter()
+            .map(|item| item.label().len() as u16)
+            .max()
+            .unwrap_or(0)
+            + 4;
+        let height = MenuItem::ALL.len() as u16 + 2;
+        // open at the click, shifted back inside the frame near the edges
+        let x = menu.column.min(area.right().saturating_sub(width));
+        let y = menu.row.min(area.bottom().saturating_sub(height));
+        app.menu_rect = Rect::new(x, y, width, height).intersection(area);
+        Clear.render(app.menu_rect, buf);
+        let items = MenuItem::ALL.map(|item| format!(" {} ", item.label()));
+        StatefulWidget::render(
+            List::new(items)
+                .block(Block::bordered().style(app.theme.border(true)))
+                .style(app.theme.panel())
+                .highlight_style(app.theme.selection(true)),
+            app.menu_rect,
+            buf,
+            &mut ListState::default().with_selected(Some(menu.selected)),
+        );
+    }
+
+    if let Some(scroll) = app.help_scroll {
+        // listed straight from the dispatch table, so overrides from config show up too
+        let lines: Vec<Line> = app
+            .keybindings
+            .iter()
+            .filter(|(action, _)| *action != Action::DumpState)
+            .map(|(action, key)| {
+                Line::from(vec![
+                    Span::styled(format!("{:>16}  ", key.to_string()), app.theme.badge()),
+                    Span::raw(action.description()),
+                ])
+            })
+            .collect();
+        let width = 60.min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let popup = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        let max_scroll = (lines.len() as u16).saturating_sub(height.saturating_sub(2));
+        let scroll = scroll.min(max_scroll);
+        app.help_scroll = Some(scroll);
+        Clear.render(popup, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .title("Keys (Esc to close)")
+                    .style(app.theme.border(true)),
+            )
+            .style(app.theme.panel())
+            .scroll((scroll, 0))
+            .render(popup, buf);
+    }
+
+    if let Some(confirm) = &app.confirm {
+        let width = (confirm.prompt.len() as u16 + 12).min(area.width);
+        let popup = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + area.height.saturating_sub(3) / 2,
+            width,
+            3,
+        )
+        .intersection(area);
+        Clear.render(popup, buf);
+        Paragraph::new(format!("{} [y/n]", confirm.prompt))
+            .block(Block::bordered().style(app.theme.border(true)))
+            .style(app.theme.panel())
+            .alignment(Alignment::Center)
+            .render(popup, buf);
+    }
+}
+
+/// Border style of a focusable panel: highlighted while focused and `active`, hinted while
+/// the mouse pointer is over it.
+fn panel_border(app: &App, panel: FocusMode, active: bool) -> Style {
+    if active && app.focus_mode == panel {
+        app.theme.border(true)
+    } else if app.hovered == Some(panel) {
+        app.theme.hover_border()
+    } else {
+        app.theme.border(false)
+    }
+}
+
+/// A panel frame with `title`, or a bare block in compact mode.
+fn panel_block<'a>(app: &App, title: impl Into<Line<'a>>) -> Block<'a> {
+    if app.compact {
+        Block::default()
+    } else {
+        Block::bordered().title(title)
+    }
+}
+
+/// Draws the frame, messages and scrollbar of the pane in `console_rect`. Only the active
+/// pane is highlighted while the console has focus.
+fn render_console_pane(app: &mut App, active: bool, buf: &mut Buffer) {
+    let max_scroll = app.max_console_scroll();
+    if app.follow {
+        app.console_scroll = max_scroll;
+    }
+    app.console_scroll = app.console_scroll.min(max_scroll);
+    panel_block(
+        app,
+        if app.console_scroll < max_scroll {
+            "Console [PAUSED]"
+        } else {
+            "Console"
+        },
+    )
+    .style(panel_border(app, FocusMode::Console, active))
+    .render(app.console_rect, buf);
+    let inner = app.console_inner();
+    render_messages(app, inner, buf);
+
+    app.scrollbar_rect = app.scrollbar_track();
+    if !app.scrollbar_rect.is_empty() {
+        let mut state = ScrollbarState::new(max_scroll + 1)
+            .position(app.console_scroll)
+            .viewport_content_length(app.scrollbar_rect.height as usize);
+        Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .render(app.scrollbar_rect, buf, &mut state);
+    }
+}
+
+/// Renders the messages visible from `console_scroll` directly into the buffer, wrapping
+/// long messages at the console width. Only the visible slice of `messages` is styled.
+pub(super) fn render_messages(app: &App, area: Rect, buf: &mut Buffer) {
+    let gutter = app.gutter_width().min(area.width);
+    let area = Rect {
+        x: area.x + gutter,
+        width: area.width - gutter,
+        ..area
+    };
+    if area.width == 0 || area.height == 0 {
+        return;
+    }
+    let width = area.width as usize;
+    let start = app.console_scroll.min(app.messages.len());
+    // every message takes at least one row, so no more than `height` can be visible
+    let end = (start + area.height as usize).min(app.messages.len());
+
+    let cjk = app.config.cjk_ambiguous_width;
+    let selection_style = app.theme.selection(app.focus_mode == FocusMode::Console);
+    // flip reverse video so that the visual mode cursor stands out from the selection
+    let cursor_style = if selection_style.add_modifier.contains(Modifier::REVERSED) {
+        selection_style
+            .remove_modifier(Modifier::REVERSED)
+            .add_modifier(Modifier::UNDERLINED)
+    } else {
+        selection_style.add_modifier(Modifier::REVERSED)
+    };
+    let gutter_style = Style::default()
+        .fg(app.theme.console_text)
+        .add_modifier(Modifier::DIM);
+    let number_width = app.line_number_width().min(gutter);
+    let timestamp_width = gutter - number_width;
+    let mut y = area.y;
+    'messages: for (offset, message) in app.messages.range(start..end).enumerate() {
+        let line_index = start + offset;
+        if number_width > 0 && y < area.bottom() {
+            let number = format!(
+                "{:>width$}",
+                line_index + 1,
+                width = number_width as usize - 1
+            );
+            buf.set_string(area.x - gutter, y, number, gutter_style);
+        }
+        if let Some(format) = app.config.timestamp_format.as_deref() {
+            if timestamp_width > 1 && y < area.bottom() {
+                let timestamp = message.timestamp(format).unwrap_or_default();
+                buf.set_stringn(
+                    area.x - timestamp_width,
+                    y,
+                    timestamp,
+                    timestamp_width as usize - 1,
+                    gutter_style,
+                );
+            }
+        }
+        let mut text_style = app
+            .theme
+            .console_line(message.level)
+            .patch(app.highlight_style(&message.text));
+        let text_width = app.text_width(message, width);
+        let indent = (width - text_width) as u16;
+        if indent > 0 && y < area.bottom() {
+            buf.set_stringn(
+                area.x,
+                y,
+                &app.config.input_echo_prefix,
+                indent as usize,
+                text_style,
+            );
+        }
+        if app.focus_mode == FocusMode::Console && app.console_cursor == Some(line_index) {
+            text_style = text_style.add_modifier(Modifier::UNDERLINED);
+        }
+        let chars: Vec<char> = message.text.chars().collect();
+        let starts = row_starts(&message.text, text_width, cjk);
+        for (row, &row_start) in starts.iter().enumerate() {
+            if y >= area.bottom() {
+                break 'messages;
+            }
+            let row_end = starts.get(row + 1).copied().unwrap_or(chars.len());
+            let mut x = area.x + indent;
+            for (offset, ch) in chars[row_start..row_end].iter().enumerate() {
+                let style = if app.visual_cursor == Some((line_index, row_start + offset)) {
+                    cursor_style
+                } else if app.is_selected(line_index, row_start + offset) {
+                    selection_style
+                } else {
+                    text_style
+                };
+                let ch_width = char_width(*ch, cjk) as u16;
+                if ch_width == 0 {
+                    // combining marks join the glyph before them
+                    if x > area.x + indent {
+                        let cell = buf.get_mut(x - 1, y);
+                        let symbol = format!("{}{ch}", cell.symbol());
+                        cell.set_symbol(&symbol);
+                    }
+                    continue;
+                }
+                buf.get_mut(x, y).set_char(*ch).set_style(style);
+                // the terminal draws the glyph across the next cell too
+                for skipped in x + 1..x + ch_width {
+                    buf.get_mut(skipped, y).reset();
+                    buf.get_mut(skipped, y).set_style(style);
+                }
+                x += ch_width;
+            }
+            y += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    fn render(app: &mut App, area: Rect, draw: fn(&mut App, Rect, &mut Buffer)) -> Vec<String> {
+        let mut buf = Buffer::empty(area);
+        draw(app, area, &mut buf);
+        (area.top()..area.bottom())
+            .map(|y| {
+                (area.left()..area.right())
+                    .map(|x| buf.get(x, y).symbol())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn snapshot_app(focus_mode: FocusMode) -> TestBackend {
+        let mut app = App::new();
+        // the logger widget draws whatever the process has logged so far
+        app.config.log_panel = LogPanelPosition::Hidden;
+        app.messages = ["hello", "world"].map(Into::into).into();
+        app.input = "draft".into();
+        app.focus_mode = focus_mode;
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        app.draw(&mut terminal).unwrap();
+        terminal.backend().clone()
+    }
+
+    #[test]
+    fn snapshot_input_focus() {
+        insta::assert_snapshot!(snapshot_app(FocusMode::Input));
+    }
+
+    #[test]
+    fn snapshot_console_focus() {
+        insta::assert_snapshot!(snapshot_app(FocusMode::Console));
+    }
+
+    #[test]
+    fn degenerate_terminal_sizes_draw_nothing_clickable() {
+        for (width, height) in [(0, 0), (1, 1), (0, 24), (80, 0)] {
+            let mut app = App::new();
+            app.messages = ["hello"].map(Into::into).into();
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            app.draw(&mut terminal).unwrap();
+            assert!(app.input_rect.is_empty());
+            assert!(app.console_rect.is_empty());
+            assert!(app.status_rect.is_empty());
+        }
+
+        // the pieces are laid out into empty rects too, e.g. a collapsed sidebar
+        let mut app = App::new();
+        app.messages = ["hello"].map(Into::into).into();
+        app.input = "text".into();
+        for area in [
+            Rect::default(),
+            Rect::new(3, 3, 1, 1),
+            Rect::new(0, 0, 2, 0),
+        ] {
+            let mut buf = Buffer::empty(Rect::new(0, 0, 5, 5));
+            render_left_column(&app, area, &mut buf);
+            render_logs(&app, area, &mut buf);
+            render_console(&mut app, area, &mut buf);
+            render_input(&mut app, area, &mut buf);
+            render_status(&mut app, area, &mut buf);
+            render_footer(&app, area, &mut buf);
+        }
+    }
+
+    #[test]
+    fn left_column_stacks_panels_logs_and_gauge() {
+        let rows = render(
+            &mut App::new(),
+            Rect::new(0, 0, 30, 30),
+            |app, area, buf| render_left_column(app, area, buf),
+        );
+        let top = |title: &str| rows.iter().position(|row| row.contains(title));
+        assert!(top("Logo") < top("Session Info"));
+        assert!(top("Session Info") < top("Logs"));
+        assert_eq!(top("Progress"), Some(27));
+    }
+
+    #[test]
+    fn log_panel_position_moves_logs_around_the_console() {
+        let logs_at = |position| {
+            let mut app = App::new();
+            app.config.log_panel = position;
+            let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+            app.draw(&mut terminal).unwrap();
+            let buf = terminal.backend().buffer();
+            let logs = (0..24).find_map(|y| {
+                let row: String = (0..80).map(|x| buf.get(x, y).symbol()).collect();
+                row.find("Logs").map(|x| (x as u16, y))
+            });
+            (logs, app.console_rect, app.input_rect)
+        };
+
+        let (logs, console, _) = logs_at(LogPanelPosition::Left);
+        assert!(logs.unwrap().0 < console.x);
+        let (logs, console, input) = logs_at(LogPanelPosition::Right);
+        assert!(logs.unwrap().0 > console.right());
+        assert_eq!(input.right(), console.right());
+        let (logs, console, input) = logs_at(LogPanelPosition::Bottom);
+        assert!(logs.unwrap().1 > input.bottom());
+        assert_eq!(console.bottom(), input.y);
+        let (logs, console, _) = logs_at(LogPanelPosition::Hidden);
+        assert_eq!(logs, None);
+        assert_eq!(console.bottom(), 24 - 5);
+    }
+
+    #[test]
+    fn console_shows_messages_inside_its_frame() {
+        let mut app = App::new();
+        app.messages = ["first", "second"].map(Into::into).into();
+        let rows = render(&mut app, Rect::new(0, 0, 20, 4), render_console);
+        assert!(rows[0].starts_with("┌Console"));
+        assert!(rows[1].starts_with("│first "));
+        assert!(rows[2].starts_with("│second "));
+        assert_eq!(app.console_rect, Rect::new(0, 0, 20, 4));
+    }
+
+    #[test]
+    fn input_draws_prompt_before_text() {
+        let mut app = App::new();
+        app.input = "hi".into();
+        let rows = render(&mut app, Rect::new(0, 0, 20, 3), render_input);
+        assert!(rows[0].starts_with("┌Input"));
+        assert!(rows[1].starts_with("│> hi "));
+        assert_eq!(app.input_rect, Rect::new(0, 0, 20, 3));
+    }
+
+    #[test]
+    fn input_counts_characters_against_the_limit() {
+        let mut app = App::new();
+        app.input = "héllo".into();
+        let rows = render(&mut app, Rect::new(0, 0, 20, 3), render_input);
+        assert!(rows[0].ends_with("5┐"));
+
+        app.config.input_max_length = Some(4);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 3));
+        render_input(&mut app, buf.area, &mut buf);
+        let counter: String = (16..19).map(|x| buf.get(x, 0).symbol()).collect();
+        assert_eq!(counter, "5/4");
+        assert_eq!(buf.get(16, 0).fg, app.theme.console_error);
+    }
+
+    #[test]
+    fn input_marks_text_scrolled_out_of_view() {
+        let mut app = App::new();
+        app.input = "0123456789abcdefghij".into();
+        let rows = render(&mut app, Rect::new(0, 0, 12, 3), render_input);
+        assert_eq!(rows[1], "│> <efghij │");
+
+        app.input = app.input.clone().with_cursor(0);
+        let rows = render(&mut app, Rect::new(0, 0, 12, 3), render_input);
+        assert_eq!(rows[1], "│> 0123456>│");
+
+        app.input = "short".into();
+        let rows = render(&mut app, Rect::new(0, 0, 12, 3), render_input);
+        assert_eq!(rows[1], "│> short   │");
+    }
+
+    #[test]
+    fn inverse_selection_reverses_instead_of_coloring() {
+        let mut app = App::new();
+        app.theme.inverse_selection = true;
+        app.input = "again".into();
+        app.input_selected = true;
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 3));
+        render_input(&mut app, buf.area, &mut buf);
+        let cell = buf.get(3, 1);
+        assert_eq!(cell.symbol(), "a");
+        assert!(cell.modifier.contains(Modifier::REVERSED));
+        assert_ne!(cell.bg, app.theme.selection_bg);
+    }
+
+    #[test]
+    fn status_shows_focus_and_message() {
+        let mut app = App::new();
+        app.status_message = Some("ready".to_string());
+        let rows = render(&mut app, Rect::new(0, 0, 60, 1), render_status);
+        assert!(rows[0].starts_with(&format!(" {} ", app.focus_mode)));
+        assert!(rows[0].trim_end().ends_with("ready"));
+    }
+}
@@ -0,0 +1,19 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Also write each submitted line as a JSON object to the given file or named pipe, or
+    /// without a path to stderr, which must then be redirected away from the terminal
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "-")]
+    pub emit_json: Option<PathBuf>,
+    /// Draw in HEIGHT rows below the prompt instead of taking over the screen, keeping the
+    /// scrollback. F9 switches between the two at runtime
+    #[arg(long, value_name = "HEIGHT")]
+    pub inline: Option<u16>,
+    /// Run the `:` commands in FILE, one per line, before starting, e.g. `theme light` or
+    /// `echo hello`
+    #[arg(long, value_name = "FILE")]
+    pub script: Option<PathBuf>,
+}
@@ -0,0 +1,86 @@
+use crate::app::AppEvent;
+use crate::errors::AppError;
+use arboard::Clipboard;
+use std::sync::mpsc;
+use std::thread;
+
+/// Which system selection a copy is written to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    /// The regular clipboard used by explicit copy/paste.
+    #[default]
+    Clipboard,
+    /// The X11/Wayland primary selection, pasted with a middle click.
+    Primary,
+}
+
+/// Owns the system clipboard on a background thread so that a slow clipboard backend never
+/// blocks rendering. Each copy is answered with an [`AppEvent::CopyResult`].
+pub struct ClipboardWorker {
+    jobs: mpsc::Sender<(String, Selection)>,
+}
+
+impl ClipboardWorker {
+    pub fn spawn(tx: mpsc::Sender<AppEvent>) -> ClipboardWorker {
+        let (jobs, job_rx) = mpsc::channel::<(String, Selection)>();
+        let span = tracing::info_span!("task", component = "clipboard");
+        thread::spawn(move || {
+            let _entered = span.enter();
+            let mut clipboard = None;
+            for (text, selection) in job_rx {
+                let result = match clipboard.as_mut() {
+                    Some(clipboard) => copy(clipboard, &text, selection),
+                    None => Clipboard::new()
+                        .map_err(AppError::from)
+                        .and_then(|opened| copy(clipboard.insert(opened), &text, selection)),
+                };
+                let result = result
+                    .map(|_| text.chars().count())
+                    .map_err(|err| err.to_string());
+                if tx.send(AppEvent::CopyResult(result)).is_err() {
+                    break;
+                }
+            }
+        });
+        ClipboardWorker { jobs }
+    }
+
+    /// Queues a copy. Fails only if the worker thread has stopped.
+    pub fn copy(&self, text: String, selection: Selection) -> Result<(), AppError> {
+        self.jobs
+            .send((text, selection))
+            .map_err(|_| AppError::TaskChannelClosed("clipboard worker"))
+    }
+}
+
+/// Writes `text` to the requested selection. The clipboard handle is kept by the caller
+/// because on X11 and Wayland the contents are only served while it is alive.
+pub fn copy(clipboard: &mut Clipboard, text: &str, selection: Selection) -> Result<(), AppError> {
+    let result = match selection {
+        Selection::Clipboard => clipboard.set_text(text),
+        Selection::Primary => copy_primary(clipboard, text),
+    };
+    Ok(result?)
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+))]
+fn copy_primary(clipboard: &mut Clipboard, text: &str) -> Result<(), arboard::Error> {
+    use arboard::{LinuxClipboardKind, SetExtLinux};
+
+    clipboard
+        .set()
+        .clipboard(LinuxClipboardKind::Primary)
+        .text(text)
+}
+
+#[cfg(not(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+)))]
+fn copy_primary(clipboard: &mut Clipboard, text: &str) -> Result<(), arboard::Error> {
+    tracing::info!("Primary selection is not supported on this platform, using the clipboard");
+    clipboard.set_text(text)
+}
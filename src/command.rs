@@ -0,0 +1,112 @@
+use log::LevelFilter;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A command typed on the `:` command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Quit,
+    Clear,
+    /// Write the console to a timestamped file in the data directory.
+    Export,
+    Theme(String),
+    Level(LevelFilter),
+    /// Print the rest of the line to the console.
+    Echo(String),
+}
+
+impl Command {
+    /// Whether running the command again could lose something, e.g. console output.
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, Command::Quit | Command::Clear)
+    }
+}
+
+impl Display for Command {
+    /// Writes the command as typed, without the leading `:`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Command::Quit => write!(f, "quit"),
+            Command::Clear => write!(f, "clear"),
+            Command::Export => write!(f, "export"),
+            Command::Theme(name) => write!(f, "theme {name}"),
+            Command::Level(level) => write!(f, "level {}", level.as_str().to_lowercase()),
+            Command::Echo(text) => write!(f, "echo {text}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    UnknownCommand(String),
+    MissingArgument(&'static str),
+    InvalidLevel(String),
+    UnexpectedArgument(String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty command"),
+            ParseError::UnknownCommand(name) => write!(f, "unknown command: {name}"),
+            ParseError::MissingArgument(name) => write!(f, "missing argument: <{name}>"),
+            ParseError::InvalidLevel(level) => write!(f, "invalid level: {level}"),
+            ParseError::UnexpectedArgument(arg) => write!(f, "unexpected argument: {arg}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for Command {
+    type Err = ParseError;
+
+    /// Parses a command line such as `:level debug`. The leading `:` is optional.
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let line = line.strip_prefix(':').unwrap_or(line).trim_start();
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or(ParseError::Empty)?;
+
+        let command = match name {
+            "q" | "quit" => Command::Quit,
+            "clear" => Command::Clear,
+            // the text is free-form, so it skips the check for extra arguments below
+            "echo" => return Ok(Command::Echo(line[name.len()..].trim().to_string())),
+            "export" => Command::Export,
+            "theme" => {
+                let theme = words.next().ok_or(ParseError::MissingArgument("name"))?;
+                Command::Theme(theme.to_string())
+            }
+            "level" => {
+                let level = words.next().ok_or(ParseError::MissingArgument("level"))?;
+                let level = LevelFilter::from_str(level)
+                    .map_err(|_| ParseError::InvalidLevel(level.to_string()))?;
+                Command::Level(level)
+            }
+            _ => return Err(ParseError::UnknownCommand(name.to_string())),
+        };
+
+        match words.next() {
+            Some(arg) => Err(ParseError::UnexpectedArgument(arg.to_string())),
+            None => Ok(command),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn echo_keeps_the_text_after_the_name() {
+        for line in [":echo hi", ": echo hi", "  :  echo   hi ", "echo hi"] {
+            assert_eq!(
+                line.parse(),
+                Ok(Command::Echo("hi".to_string())),
+                "{line:?}"
+            );
+        }
+    }
+}
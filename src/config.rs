@@ -1,8 +1,21 @@
+use crate::action::Action;
+use crate::app::FocusMode;
+use crate::errors::AppError;
+use crate::keybindings::KeyBinding;
+use crate::theme::Theme;
+use log::LevelFilter;
+use ratatui::style::{Color, Modifier, Style};
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::LazyLock;
 
 pub static PROJECT_NAME: LazyLock<String> =
     LazyLock::new(|| env!("CARGO_CRATE_NAME").to_uppercase().to_string());
+static CONFIG_FILE: LazyLock<String> = LazyLock::new(|| "config.toml".to_string());
+static HISTORY_FILE: LazyLock<String> = LazyLock::new(|| "history.txt".to_string());
+
 pub fn get_data_dir() -> PathBuf {
     PathBuf::from(".")
     // let directory = if let Some(s) = DATA_FOLDER.clone() {
@@ -14,3 +27,371 @@ pub fn get_data_dir() -> PathBuf {
     // };
     // directory
 }
+
+pub fn get_history_path() -> PathBuf {
+    get_data_dir().join(HISTORY_FILE.clone())
+}
+
+/// User settings read from `config.toml` in the data directory. Every field is optional and
+/// falls back to its default when missing.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Feedback given when a key has no binding in the focused panel.
+    pub bell: BellStyle,
+    /// Whether to use the theme's colors or the monochrome theme.
+    pub color: ColorMode,
+    pub theme: Theme,
+    /// Number of submitted lines kept in the input history file.
+    pub history_size: usize,
+    /// Don't add a line to the history when it repeats the previous one.
+    pub history_ignore_dups: bool,
+    /// Don't add lines starting with a space to the history, to keep one-offs out of it.
+    pub history_ignore_space: bool,
+    /// Prompt drawn before the line being edited, e.g. `"> "`. Empty for none.
+    pub input_prompt: String,
+    /// Fill in `{level}`, `{tab}`, `{focus}` and `{messages}` in `input_prompt` on every
+    /// draw, e.g. `"[{level}]> "`. Other braces are kept as written.
+    pub input_prompt_template: bool,
+    /// Dimmed hint shown while the input is empty.
+    pub input_placeholder: String,
+    /// Panel focused at startup, `input` or `console` for mostly reading output.
+    pub initial_focus: FocusMode,
+    /// What is left in the input after a line is submitted.
+    pub submit_behavior: SubmitBehavior,
+    /// Send an empty input as a blank line when `Enter` is pressed. Otherwise `Enter` does
+    /// `empty_enter` instead.
+    pub submit_empty: bool,
+    /// What `Enter` on an empty input does while `submit_empty` is off.
+    pub empty_enter: EmptyEnter,
+    /// Accept submitting a line of only spaces, or an empty one with `submit_empty`.
+    pub allow_empty_input: bool,
+    /// Reject submitted lines longer than this many characters. The counter on the input
+    /// border turns red past it.
+    pub input_max_length: Option<usize>,
+    /// Reject submitted lines that this regular expression does not match, e.g.
+    /// `"^[a-z ]+$"`. Anchor it to check the whole line.
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub input_pattern: Option<Regex>,
+    /// Number of console lines kept before the oldest are dropped.
+    pub max_messages: usize,
+    /// Also show log records at or above this level in the console, e.g. `"warn"`. Off when
+    /// unset; the log panel shows them either way.
+    pub console_log_level: Option<LevelFilter>,
+    /// Whether tasks may rewrite the newest console line, e.g. for `\r`-style progress text.
+    pub console_mode: ConsoleMode,
+    /// Start with the multiline input editor enabled (toggle with F2).
+    pub multiline_input: bool,
+    /// How copied console lines are joined and terminated.
+    pub copy_line_ending: CopyLineEnding,
+    /// Copy a console selection to the primary selection as soon as the mouse is released.
+    pub copy_on_select: bool,
+    /// Clear the console selection once it has been copied to the clipboard.
+    pub clear_selection_after_copy: bool,
+    /// Let `Ctrl+C` quit when there is no console selection for it to copy.
+    pub ctrl_c_quits: bool,
+    /// Start with the mouse captured only over the console (toggle with Shift+F10). Moving it
+    /// elsewhere hands it to the terminal's own selection until the next key press.
+    pub mouse_console_only: bool,
+    /// Ask before repeating a command that loses something, such as `:clear`, with `.`.
+    pub confirm_repeat: bool,
+    /// Ask before quitting with `Ctrl+C`.
+    pub confirm_quit: bool,
+    /// Ask before clearing the console with `Ctrl+L`, `:clear` or the context menu.
+    pub confirm_clear: bool,
+    /// Initial width of the left column in percent, between 10 and 60.
+    pub left_column_percent: u16,
+    /// Rows taken by the inline viewport when switching from the alternate screen with F9.
+    /// `--inline` overrides it. Below 10 only the size warning fits.
+    pub inline_height: u16,
+    /// Lines moved per arrow key or mouse wheel notch in the console.
+    pub scroll_step: usize,
+    /// Keys for global actions, e.g. `quit = "ctrl+q"`. Unlisted actions keep their defaults.
+    pub keybindings: HashMap<Action, KeyBinding>,
+    /// Tabs of the log panel, each showing one log target or everything.
+    pub log_tabs: Vec<LogTab>,
+    /// Where the log panel is drawn, if at all.
+    pub log_panel: LogPanelPosition,
+    /// Interval between ticks that expire toasts and other time-based state.
+    pub tick_rate_ms: u64,
+    /// How long feedback such as copy results and errors stays in the footer.
+    pub toast_duration_ms: u64,
+    /// Run `idle_action` after this many seconds without key or mouse input, e.g. to end
+    /// unattended demos. Never when unset.
+    pub idle_timeout_secs: Option<u64>,
+    /// What an idle timeout does, `quit` by default.
+    pub idle_action: Action,
+    /// Redraw on every tick even when nothing changed, at the cost of idle CPU.
+    pub redraw_when_idle: bool,
+    /// Share of the remaining distance the progress gauge moves per tick, from 0 (jump
+    /// straight to each update) to 1.
+    pub progress_smoothing: f64,
+    /// strftime format of a timestamp column before each console line, e.g. `"%H:%M:%S"`.
+    /// No column when unset.
+    pub timestamp_format: Option<String>,
+    /// Include the timestamp column when copying console text.
+    pub copy_timestamps: bool,
+    /// Styles for console lines matching a pattern, e.g.
+    /// `highlights = [{ pattern = "ERROR", fg = "red" }]`. Later matching rules add to
+    /// earlier ones. Invalid patterns are skipped with a warning.
+    pub highlights: Vec<HighlightRule>,
+    /// Drawn before console lines the user submitted, in the theme's `console_input` color.
+    /// Empty for none.
+    pub input_echo_prefix: String,
+    /// Include `input_echo_prefix` when copying submitted lines.
+    pub copy_input_prefix: bool,
+    /// Count East Asian ambiguous-width characters as two columns, for terminals set up for
+    /// CJK text.
+    pub cjk_ambiguous_width: bool,
+    /// Show message numbers in a gutter left of the console (toggle with `n`).
+    pub line_numbers: bool,
+    /// Start without panel borders and titles (toggle with F8).
+    pub compact: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            bell: BellStyle::default(),
+            color: ColorMode::default(),
+            theme: Theme::default(),
+            history_size: 1000,
+            history_ignore_dups: true,
+            history_ignore_space: false,
+            max_messages: 10_000,
+            console_log_level: None,
+            console_mode: ConsoleMode::default(),
+            input_prompt: "> ".to_string(),
+            input_prompt_template: false,
+            input_placeholder: "Type a message, or : for a command".to_string(),
+            initial_focus: FocusMode::default(),
+            submit_behavior: SubmitBehavior::default(),
+            submit_empty: false,
+            empty_enter: EmptyEnter::default(),
+            allow_empty_input: true,
+            input_max_length: None,
+            input_pattern: None,
+            multiline_input: false,
+            copy_on_select: false,
+            clear_selection_after_copy: true,
+            copy_line_ending: CopyLineEnding::default(),
+            ctrl_c_quits: true,
+            mouse_console_only: false,
+            confirm_repeat: true,
+            confirm_quit: true,
+            confirm_clear: true,
+            left_column_percent: 25,
+            line_numbers: false,
+            compact: false,
+            timestamp_format: None,
+            copy_timestamps: false,
+            highlights: vec![],
+            input_echo_prefix: "> ".to_string(),
+            copy_input_prefix: false,
+            cjk_ambiguous_width: false,
+            inline_height: 12,
+            scroll_step: 1,
+            keybindings: HashMap::new(),
+            log_tabs: vec![
+                LogTab::all("All"),
+                LogTab::target("App"),
+                LogTab::target("crossterm"),
+                LogTab::target("progress-task"),
+            ],
+            log_panel: LogPanelPosition::default(),
+            tick_rate_ms: 250,
+            toast_duration_ms: 3000,
+            idle_timeout_secs: None,
+            idle_action: Action::Quit,
+            redraw_when_idle: false,
+            progress_smoothing: 0.5,
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Config, AppError> {
+        let path = get_data_dir().join(CONFIG_FILE.clone());
+        let config = ::config::Config::builder()
+            .add_source(::config::File::from(path).required(false))
+            .build()?
+            .try_deserialize()?;
+        Ok(config)
+    }
+}
+
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|pattern| Regex::new(&pattern).map_err(de::Error::custom))
+        .transpose()
+}
+
+/// A log panel tab, configured in `config.toml` as e.g.
+/// `log_tabs = [{ name = "App", target = "App", level = "debug" }]`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LogTab {
+    pub name: String,
+    /// Log target to show; every target when unset.
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Most verbose level shown; follows the `:level` command when unset.
+    #[serde(default)]
+    pub level: Option<LevelFilter>,
+}
+
+impl LogTab {
+    fn all(name: &str) -> LogTab {
+        LogTab {
+            name: name.to_string(),
+            target: None,
+            level: None,
+        }
+    }
+
+    fn target(target: &str) -> LogTab {
+        LogTab {
+            target: Some(target.to_string()),
+            ..LogTab::all(target)
+        }
+    }
+}
+
+/// Restyles whole console lines whose text matches `pattern`, whatever their level.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct HighlightRule {
+    /// Regular expression searched for anywhere in the line.
+    pub pattern: String,
+    #[serde(default)]
+    pub fg: Option<Color>,
+    #[serde(default)]
+    pub bg: Option<Color>,
+    #[serde(default)]
+    pub bold: bool,
+}
+
+impl HighlightRule {
+    pub fn style(&self) -> Style {
+        let mut style = Style::default();
+        if let Some(fg) = self.fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = self.bg {
+            style = style.bg(bg);
+        }
+        if self.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        style
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogPanelPosition {
+    /// Between the panels and the progress gauge in the left column.
+    #[default]
+    Left,
+    /// A sidebar right of the console, as wide as the left column.
+    Right,
+    /// A strip across the bottom, above the status bar.
+    Bottom,
+    Hidden,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BellStyle {
+    /// Ignore unbound keys silently.
+    #[default]
+    None,
+    /// Invert the status bar for one frame.
+    Visual,
+    /// Ring the terminal bell.
+    Audible,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// Colors unless `NO_COLOR` is set or `TERM` is `dumb`.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorMode::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                !no_color && std::env::var_os("TERM").is_none_or(|term| term != "dumb")
+            }
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SubmitBehavior {
+    /// Start over with an empty input.
+    #[default]
+    Clear,
+    /// Leave the text for editing or sending again.
+    Keep,
+    /// Leave the text selected, so typing replaces it and `Enter` sends it again.
+    SelectAll,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EmptyEnter {
+    /// Nothing happens.
+    #[default]
+    Ignore,
+    /// Focus moves to the console, e.g. to start scrolling.
+    FocusConsole,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConsoleMode {
+    /// Lines are only ever added; a request to replace the last line appends instead.
+    #[default]
+    Append,
+    /// The newest line can be rewritten in place with [`crate::AppEvent::ConsoleUpdate`].
+    Editable,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CopyLineEnding {
+    /// Every line, including the last, ends with `\n`.
+    Lf,
+    /// Every line, including the last, ends with `\r\n`.
+    Crlf,
+    /// Lines are separated by `\n` with nothing after the last one.
+    #[default]
+    NoneOnLast,
+}
+
+impl CopyLineEnding {
+    /// Joins `lines` for the clipboard. Any `\r` left at the end of a line, e.g. from piped
+    /// Windows text, is dropped first so that endings are never doubled.
+    pub fn join<S: AsRef<str>>(self, lines: &[S]) -> String {
+        let lines: Vec<&str> = lines
+            .iter()
+            .map(|line| line.as_ref().trim_end_matches('\r'))
+            .collect();
+        match self {
+            CopyLineEnding::Lf => lines.iter().map(|line| format!("{line}\n")).collect(),
+            CopyLineEnding::Crlf => lines.iter().map(|line| format!("{line}\r\n")).collect(),
+            CopyLineEnding::NoneOnLast => lines.join("\n"),
+        }
+    }
+}
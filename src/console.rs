@@ -0,0 +1,64 @@
+use chrono::{DateTime, Local};
+use std::fmt::Write;
+
+/// Where a console line came from, which decides how it is styled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineLevel {
+    /// Output such as piped stdin.
+    #[default]
+    Info,
+    /// A line the user submitted from the input box.
+    Input,
+    /// A failure reported by a background task or logged at error level.
+    Error,
+}
+
+/// One console message with its level and the time it was added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleLine {
+    pub text: String,
+    pub level: LineLevel,
+    pub time: DateTime<Local>,
+}
+
+impl ConsoleLine {
+    pub fn new(text: impl Into<String>, level: LineLevel) -> ConsoleLine {
+        ConsoleLine {
+            text: text.into(),
+            level,
+            time: Local::now(),
+        }
+    }
+
+    /// The time the line was added in strftime `format`, or `None` if the format is invalid.
+    pub fn timestamp(&self, format: &str) -> Option<String> {
+        format_time(&self.time, format)
+    }
+
+    pub fn input(text: impl Into<String>) -> ConsoleLine {
+        ConsoleLine::new(text, LineLevel::Input)
+    }
+
+    pub fn error(text: impl Into<String>) -> ConsoleLine {
+        ConsoleLine::new(text, LineLevel::Error)
+    }
+}
+
+impl From<&str> for ConsoleLine {
+    fn from(text: &str) -> Self {
+        ConsoleLine::new(text, LineLevel::Info)
+    }
+}
+
+impl From<String> for ConsoleLine {
+    fn from(text: String) -> Self {
+        ConsoleLine::new(text, LineLevel::Info)
+    }
+}
+
+/// Formats `time` without panicking on an invalid strftime string, unlike `to_string`.
+pub fn format_time(time: &DateTime<Local>, format: &str) -> Option<String> {
+    let mut formatted = String::new();
+    write!(formatted, "{}", time.format(format)).ok()?;
+    Some(formatted)
+}
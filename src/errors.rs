@@ -1,9 +1,77 @@
+use std::fmt::{self, Display, Formatter};
+use std::io;
 use std::panic;
 
 use color_eyre::{config::HookBuilder, eyre};
 
 use crate::tui;
 
+/// Failures from the app's fallible entry points, for callers that want to tell them apart.
+/// The binary wraps these into `eyre` reports at the top level.
+#[derive(Debug)]
+pub enum AppError {
+    /// Entering raw mode or the alternate screen failed.
+    TerminalInit(io::Error),
+    /// A background task or worker hung up its end of a channel.
+    TaskChannelClosed(&'static str),
+    /// `config.toml` exists but could not be read or parsed.
+    ConfigParse(::config::ConfigError),
+    Clipboard(arboard::Error),
+    /// A line of a `--script` file is not a valid command.
+    Script {
+        line: usize,
+        message: String,
+    },
+    /// The terminal the app runs on can't do this, e.g. a `TestBackend` running an editor.
+    Unsupported(&'static str),
+    Io(io::Error),
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::TerminalInit(err) => write!(f, "failed to initialize terminal: {err}"),
+            AppError::TaskChannelClosed(task) => write!(f, "{task} is no longer running"),
+            AppError::ConfigParse(err) => write!(f, "invalid configuration: {err}"),
+            AppError::Clipboard(err) => write!(f, "clipboard error: {err}"),
+            AppError::Script { line, message } => write!(f, "script line {line}: {message}"),
+            AppError::Unsupported(what) => write!(f, "{what} is not supported on this terminal"),
+            AppError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::TerminalInit(err) | AppError::Io(err) => Some(err),
+            AppError::ConfigParse(err) => Some(err),
+            AppError::Clipboard(err) => Some(err),
+            AppError::TaskChannelClosed(_) | AppError::Script { .. } | AppError::Unsupported(_) => {
+                None
+            }
+        }
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        AppError::Io(err)
+    }
+}
+
+impl From<::config::ConfigError> for AppError {
+    fn from(err: ::config::ConfigError) -> Self {
+        AppError::ConfigParse(err)
+    }
+}
+
+impl From<arboard::Error> for AppError {
+    fn from(err: arboard::Error) -> Self {
+        AppError::Clipboard(err)
+    }
+}
+
 /// This replaces the standard color_eyre panic and error hooks with hooks that
 /// restore the terminal before printing the panic or error.
 pub fn install_hooks() -> color_eyre::Result<()> {
@@ -0,0 +1,104 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use tracing::warn;
+
+/// Submitted input lines, recalled with Up/Down and persisted between runs as a
+/// newline-delimited file.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    entries: Vec<String>,
+    max_entries: usize,
+    /// Entry shown while browsing; `None` while editing a fresh line.
+    position: Option<usize>,
+    /// The line that was being edited when browsing started.
+    draft: String,
+    /// Skip a line identical to the newest entry, like the shell's `ignoredups`.
+    ignore_dups: bool,
+    /// Skip lines starting with a space, like the shell's `ignorespace`.
+    ignore_space: bool,
+}
+
+impl History {
+    pub fn new(max_entries: usize) -> History {
+        History {
+            max_entries,
+            ..Default::default()
+        }
+    }
+
+    /// Sets which lines [`History::push`] leaves out. Entries already stored are kept.
+    pub fn with_control(mut self, ignore_dups: bool, ignore_space: bool) -> History {
+        self.ignore_dups = ignore_dups;
+        self.ignore_space = ignore_space;
+        self
+    }
+
+    /// Reads the history file, starting empty if it is missing or unreadable.
+    pub fn load(path: &Path, max_entries: usize) -> History {
+        let mut history = History::new(max_entries);
+        match fs::read_to_string(path) {
+            Ok(contents) => contents.lines().for_each(|line| history.push(line)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => warn!("Ignoring unreadable history file {:?}: {}", path, err),
+        }
+        history
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut contents = self.entries.join("\n");
+        contents.push('\n');
+        fs::write(path, contents)
+    }
+
+    pub fn push(&mut self, line: &str) {
+        self.position = None;
+        let duplicate = self.ignore_dups && self.entries.last().is_some_and(|last| last == line);
+        if line.is_empty() || duplicate || (self.ignore_space && line.starts_with(' ')) {
+            return;
+        }
+        self.entries.push(line.to_string());
+        if self.entries.len() > self.max_entries {
+            let excess = self.entries.len() - self.max_entries;
+            self.entries.drain(..excess);
+        }
+    }
+
+    /// Finds the newest entry older than index `before` that contains `query`.
+    pub fn search(&self, query: &str, before: usize) -> Option<(usize, &str)> {
+        let before = before.min(self.entries.len());
+        self.entries[..before]
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, entry)| entry.contains(query))
+            .map(|(index, entry)| (index, entry.as_str()))
+    }
+
+    /// Steps back to an older entry. `current` is remembered when browsing starts so that
+    /// stepping forward past the newest entry restores it.
+    pub fn previous(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(position) => position.saturating_sub(1),
+        };
+        self.position = Some(position);
+        Some(&self.entries[position])
+    }
+
+    /// Steps forward to a newer entry, ending with the line that was being edited.
+    pub fn next(&mut self) -> Option<&str> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            Some(&self.entries[position + 1])
+        } else {
+            self.position = None;
+            Some(&self.draft)
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use crate::action::Action;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A key chord such as `Ctrl+Q`, written in `config.toml` as `"ctrl+q"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> KeyBinding {
+        let (code, modifiers) = normalize(code, modifiers);
+        KeyBinding { code, modifiers }
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        *self == KeyBinding::new(key.code, key.modifiers)
+    }
+}
+
+/// Terminals disagree on whether Shift is reported alongside the character it produced, so
+/// letters always carry Shift and are lowercased, and other characters never carry it.
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    match code {
+        KeyCode::Char(ch) if ch.is_ascii_uppercase() => (
+            KeyCode::Char(ch.to_ascii_lowercase()),
+            modifiers | KeyModifiers::SHIFT,
+        ),
+        KeyCode::Char(ch) if !ch.is_ascii_alphabetic() => (code, modifiers - KeyModifiers::SHIFT),
+        _ => (code, modifiers),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseKeyError(String);
+
+impl Display for ParseKeyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid key binding: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseKeyError {}
+
+impl FromStr for KeyBinding {
+    type Err = ParseKeyError;
+
+    /// Parses `+`-separated modifiers followed by a key, e.g. `ctrl+shift+c`, `alt+left`,
+    /// `f10` or `?`. Case is ignored except for a lone character.
+    fn from_str(binding: &str) -> Result<Self, Self::Err> {
+        let error = || ParseKeyError(binding.to_string());
+        // split from the right so that `ctrl++` binds the `+` key
+        let (prefix, key) = match binding.rsplit_once('+') {
+            Some((prefix, "")) => (prefix.strip_suffix('+').ok_or_else(error)?, "+"),
+            Some((prefix, key)) => (prefix, key),
+            None => ("", binding),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in prefix.split('+').filter(|modifier| !modifier.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(error()),
+            };
+        }
+        let code = match key.to_ascii_lowercase().as_str() {
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            lower => match (lower.strip_prefix('f'), key.chars().count()) {
+                (Some(number), _) if !number.is_empty() => {
+                    KeyCode::F(number.parse().map_err(|_| error())?)
+                }
+                (_, 1) => KeyCode::Char(key.chars().next().ok_or_else(error)?),
+                _ => return Err(error()),
+            },
+        };
+        Ok(KeyBinding::new(code, modifiers))
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = ParseKeyError;
+
+    fn try_from(binding: String) -> Result<Self, Self::Error> {
+        binding.parse()
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(ch) => write!(f, "{}", ch.to_ascii_uppercase()),
+            KeyCode::F(number) => write!(f, "F{number}"),
+            KeyCode::Esc => f.write_str("Esc"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// Keys for the actions that work regardless of focus. Entries from `config.toml` replace the
+/// default key for their action and leave the others alone.
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    bindings: Vec<(Action, KeyBinding)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let key = |code, modifiers| KeyBinding::new(code, modifiers);
+        let mut defaults = KeyBindings {
+            bindings: vec![
+                (Action::Quit, key(KeyCode::Char('q'), KeyModifiers::CONTROL)),
+                (Action::Help, key(KeyCode::F(1), KeyModifiers::NONE)),
+                (
+                    Action::ClearScreen,
+                    key(KeyCode::Char('l'), KeyModifiers::CONTROL),
+                ),
+                (
+                    Action::CopyConsole,
+                    key(
+                        KeyCode::Char('c'),
+                        KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+                    ),
+                ),
+                (
+                    Action::ExportConsole,
+                    key(KeyCode::Char('s'), KeyModifiers::CONTROL),
+                ),
+                (Action::PreviousTab, key(KeyCode::Left, KeyModifiers::ALT)),
+                (Action::NextTab, key(KeyCode::Right, KeyModifiers::ALT)),
+                (
+                    Action::CycleLogLevel,
+                    key(KeyCode::F(5), KeyModifiers::NONE),
+                ),
+                (
+                    Action::ToggleMouseCapture,
+                    key(KeyCode::F(10), KeyModifiers::NONE),
+                ),
+                (
+                    Action::ToggleConsoleMouse,
+                    key(KeyCode::F(10), KeyModifiers::SHIFT),
+                ),
+                (
+                    Action::ToggleCompact,
+                    key(KeyCode::F(8), KeyModifiers::NONE),
+                ),
+                (Action::ToggleInline, key(KeyCode::F(9), KeyModifiers::NONE)),
+                (
+                    Action::ToggleRenderStats,
+                    key(KeyCode::F(12), KeyModifiers::NONE),
+                ),
+            ],
+        };
+        if cfg!(feature = "debug-state") {
+            defaults.bindings.push((
+                Action::DumpState,
+                key(
+                    KeyCode::Char('d'),
+                    KeyModifiers::CONTROL | KeyModifiers::ALT,
+                ),
+            ));
+        }
+        defaults
+    }
+}
+
+impl KeyBindings {
+    pub fn with_overrides(overrides: &HashMap<Action, KeyBinding>) -> KeyBindings {
+        let mut bindings = KeyBindings::default();
+        for (action, key) in overrides {
+            match bindings
+                .bindings
+                .iter_mut()
+                .find(|(bound, _)| bound == action)
+            {
+                Some((_, bound_key)) => *bound_key = *key,
+                None => bindings.bindings.push((action.clone(), *key)),
+            }
+        }
+        bindings
+    }
+
+    /// The action bound to `key`, if any.
+    pub fn action_for(&self, key: &KeyEvent) -> Option<&Action> {
+        self.bindings
+            .iter()
+            .find(|(_, binding)| binding.matches(key))
+            .map(|(action, _)| action)
+    }
+
+    pub fn key_for(&self, action: &Action) -> Option<KeyBinding> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound == action)
+            .map(|(_, key)| *key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Action, KeyBinding)> {
+        self.bindings.iter()
+    }
+}
@@ -0,0 +1,44 @@
+//! A ratatui demo app that can be embedded as a starting point for other TUIs.
+//!
+//! The binary is a thin wrapper over [`run`]:
+//!
+//! ```no_run
+//! use ratatui_demo::{config::Config, tui::init_terminal, App};
+//!
+//! fn main() -> color_eyre::Result<()> {
+//!     let mut terminal = init_terminal()?;
+//!     let result = ratatui_demo::run(&mut terminal, App::with_config(Config::load()?));
+//!     terminal.restore()?;
+//!     result.map(|_| ())
+//! }
+//! ```
+
+pub mod action;
+mod app;
+mod clipboard;
+mod command;
+pub mod config;
+pub mod console;
+pub mod errors;
+mod history;
+pub mod keybindings;
+pub mod logging;
+pub mod panel;
+pub mod sink;
+mod theme;
+pub mod tui;
+
+pub use crate::app::{App, AppBuilder, AppEvent, FocusMode, RedrawHandle, ShutdownReason};
+pub use crate::errors::AppError;
+pub use crate::theme::Theme;
+
+use crate::tui::AppTerminal;
+use color_eyre::Result;
+
+/// Runs `app` on `terminal` until the user quits or every event source has ended, and says
+/// which. `terminal` is any `Terminal`, or the [`tui::TerminalGuard`] from
+/// [`tui::init_terminal`] or [`tui::init_terminal_with`] to also allow switching viewports and
+/// running an external editor; restoring it afterwards is left to the caller.
+pub fn run(terminal: &mut impl AppTerminal, app: App) -> Result<ShutdownReason> {
+    app.start(terminal, tui::CrosstermEvents)
+}
@@ -1,10 +1,19 @@
 use color_eyre::Result;
+use log::LevelFilter;
+use std::cell::Cell;
+use std::fmt::Write;
 use std::fs::OpenOptions;
+use std::sync::mpsc::Sender;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
 use tracing_error::ErrorLayer;
-use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::{fmt, prelude::*, EnvFilter, Layer};
 
 use crate::config;
-use std::sync::LazyLock;
+use crate::console::{ConsoleLine, LineLevel};
+use crate::AppEvent;
+use std::sync::{LazyLock, Mutex};
 use tracing::Level;
 
 static LOG_FILE: LazyLock<String> = LazyLock::new(|| "demo.log".to_string());
@@ -39,7 +48,103 @@ pub fn init() -> Result<()> {
         .with(file_subscriber)
         .with(env_filter)
         .with(ErrorLayer::default())
+        .with(tui_logger::tracing_subscriber_layer())
+        .with(ConsoleLayer)
         .try_init()?;
 
     Ok(())
 }
+
+/// Where [`ConsoleLayer`] sends records, and the most verbose level it sends. Set while an
+/// app with `console_log_level` runs.
+static CONSOLE_MIRROR: Mutex<Option<(Sender<AppEvent>, LevelFilter)>> = Mutex::new(None);
+
+thread_local! {
+    /// Set while a record is being forwarded, so that anything logged on the way is dropped
+    /// rather than forwarded in turn.
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Starts mirroring log records at or above `level` into the console of the app reading `tx`.
+pub(crate) fn mirror_to_console(tx: Sender<AppEvent>, level: LevelFilter) {
+    *CONSOLE_MIRROR.lock().unwrap_or_else(|err| err.into_inner()) = Some((tx, level));
+}
+
+pub(crate) fn stop_mirroring() {
+    *CONSOLE_MIRROR.lock().unwrap_or_else(|err| err.into_inner()) = None;
+}
+
+/// Forwards log records to the console as [`AppEvent::LogRecord`] while
+/// [`mirror_to_console`] is in effect, and does nothing otherwise.
+pub(crate) struct ConsoleLayer;
+
+impl<S: Subscriber> Layer<S> for ConsoleLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if !FORWARDING.replace(true) {
+            forward(event);
+            FORWARDING.set(false);
+        }
+    }
+}
+
+fn forward(event: &Event<'_>) {
+    let metadata = event.metadata();
+    let level = match *metadata.level() {
+        Level::ERROR => log::Level::Error,
+        Level::WARN => log::Level::Warn,
+        Level::INFO => log::Level::Info,
+        Level::DEBUG => log::Level::Debug,
+        Level::TRACE => log::Level::Trace,
+    };
+    let mirror = CONSOLE_MIRROR.lock().unwrap_or_else(|err| err.into_inner());
+    let Some((tx, _)) = mirror.as_ref().filter(|(_, max)| level <= *max) else {
+        return;
+    };
+    let mut record = RecordText::default();
+    event.record(&mut record);
+    // records from the `log` macros name their real target in a field
+    let target = record.target.as_deref().unwrap_or(metadata.target());
+    let text = format!("{level} {target}: {}", record.text);
+    let line_level = match level {
+        log::Level::Error => LineLevel::Error,
+        _ => LineLevel::Info,
+    };
+    // a closed channel means the app is shutting down; nothing to report it to
+    let _ = tx.send(AppEvent::LogRecord(ConsoleLine::new(text, line_level)));
+}
+
+/// The message of a record followed by its other fields as `name=value`.
+#[derive(Default)]
+struct RecordText {
+    text: String,
+    /// Target of a record forwarded from the `log` crate.
+    target: Option<String>,
+}
+
+impl RecordText {
+    fn push(&mut self, name: &str, value: std::fmt::Arguments) {
+        match name {
+            "message" => {
+                let _ = self.text.write_fmt(value);
+            }
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.text, " {name}={value}");
+            }
+        }
+    }
+}
+
+impl Visit for RecordText {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "log.target" {
+            self.target = Some(value.to_string());
+        } else {
+            self.push(field.name(), format_args!("{value}"));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.push(field.name(), format_args!("{value:?}"));
+    }
+}
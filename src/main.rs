@@ -1,49 +1,73 @@
-mod action;
-mod app;
-mod config;
-mod errors;
-mod logging;
-mod tui;
-
-use crate::app::App;
-use crate::tui::{init_terminal, restore_terminal};
-use color_eyre::{eyre::WrapErr, Result};
-use ratatui::{
-    buffer::Buffer, crossterm::event::KeyCode, layout::Rect, style::Stylize, widgets::Widget,
+mod cli;
+
+use crate::cli::Cli;
+use clap::Parser;
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
 };
+use ratatui_demo::config::Config;
+use ratatui_demo::sink::JsonSink;
+use ratatui_demo::tui::init_terminal_with;
+use ratatui_demo::{logging, App, ShutdownReason};
+use std::io::{self, IsTerminal};
 use tracing::{debug, span, Level};
-use tui_logger::{init_logger, set_default_level};
 
 // use logging;
 
 fn main() -> Result<()> {
+    let cli = Cli::parse();
     // init_logger(LevelFilter::Trace)?;
     // set_default_level(LevelFilter::Trace);
 
     logging::init()?;
-    //
-    // let h = std::thread::spawn(|| {
-    //     let span = span!(Level::DEBUG, "foo", task = "footask");
-    //     let f = span.enter();
-    //     debug!("Logging initialized");
-    // });
-    let span = span!(Level::DEBUG, "foo", task = "initializing");
+    let span = span!(Level::INFO, "startup", phase = "init", component = "main");
     let init_span = span.enter();
     debug!("Logging initialized");
-    // h.join();
+
+    let mut config = Config::load().wrap_err("failed to load config.toml")?;
+    if let Some(height) = cli.inline {
+        config.inline_height = height;
+    }
+    let mut app = App::with_config(config);
+    if let Some(script) = &cli.script {
+        app.run_script(script)
+            .wrap_err_with(|| format!("failed to run {}", script.display()))?;
+    }
+    match cli.emit_json.as_deref() {
+        Some(path) if path.as_os_str() == "-" => {
+            // the TUI draws on the same terminal, so the JSON would garble it
+            if io::stderr().is_terminal() {
+                bail!("--emit-json without a path needs stderr redirected, e.g. 2>lines.jsonl");
+            }
+            app.add_sink(Box::new(JsonSink::stderr()))
+        }
+        Some(path) => app.add_sink(Box::new(JsonSink::open(path)?)),
+        None => {}
+    }
 
     //
-    let mut terminal = init_terminal()?;
+    let mut terminal = init_terminal_with(cli.inline)?;
     terminal.clear()?;
     drop(init_span);
     // terminal.hide_cursor()?;
     //
-    App::new().start(&mut terminal)?;
+    // worker threads nest their spans in this one
+    let reason = span!(Level::INFO, "event_loop", phase = "run", component = "app")
+        .in_scope(|| ratatui_demo::run(&mut terminal, app))?;
 
-    let span = span!(Level::DEBUG, "foo", task = "restoring");
+    let span = span!(
+        Level::INFO,
+        "shutdown",
+        phase = "restore",
+        component = "terminal"
+    );
     let _restore_span = span.enter();
-    restore_terminal()?;
+    terminal.restore()?;
     terminal.clear()?;
+    if reason == ShutdownReason::EventSourcesEnded {
+        eprintln!("ratatui_demo: all event sources ended");
+    }
 
     Ok(())
 }
@@ -0,0 +1,101 @@
+use ratatui::prelude::*;
+use ratatui::widgets::{Paragraph, Wrap};
+
+/// A panel stacked at the top of the left column, above the logs and the progress gauge.
+/// The app draws the frame and title, so `render` only fills the inside.
+pub trait Panel {
+    fn title(&self) -> &str;
+
+    /// Height of the panel, frame included.
+    fn constraint(&self) -> Constraint {
+        Constraint::Percentage(20)
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer);
+}
+
+/// The panels shown when none are configured: [`logo`] and [`session_info`].
+pub fn default_panels() -> Vec<Box<dyn Panel>> {
+    vec![Box::new(logo()), Box::new(session_info())]
+}
+
+pub fn logo() -> TextPanel {
+    TextPanel::new("Logo", sample_text())
+}
+
+pub fn session_info() -> TextPanel {
+    TextPanel::new("Session Info", sample_text())
+}
+
+fn sample_text() -> Vec<Line<'static>> {
+    vec![
+        Line::from(vec![
+            Span::raw("First"),
+            Span::styled("line", Style::new().green().italic()),
+            ".".into(),
+        ]),
+        Line::from("Second line".red()),
+        "Third line".into(),
+    ]
+}
+
+/// Centered static text.
+pub struct TextPanel {
+    title: String,
+    text: Text<'static>,
+    wrap: Option<Wrap>,
+}
+
+impl TextPanel {
+    /// Long lines wrap with leading whitespace trimmed from each row; see [`TextPanel::wrap`].
+    pub fn new(title: impl Into<String>, text: impl Into<Text<'static>>) -> TextPanel {
+        TextPanel {
+            title: title.into(),
+            text: text.into(),
+            wrap: Some(Wrap { trim: true }),
+        }
+    }
+
+    /// How long lines wrap. `trim: true` drops leading whitespace from every row, which
+    /// suits prose; `trim: false` keeps indentation, which suits aligned or ASCII-art text.
+    /// `None` cuts lines off at the edge instead of wrapping.
+    pub fn wrap(mut self, wrap: Option<Wrap>) -> TextPanel {
+        self.wrap = wrap;
+        self
+    }
+}
+
+impl Panel for TextPanel {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let paragraph = Paragraph::new(self.text.clone()).alignment(Alignment::Center);
+        match self.wrap {
+            Some(wrap) => paragraph.wrap(wrap).render(area, buf),
+            None => paragraph.render(area, buf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_row(panel: TextPanel) -> String {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 12, 2));
+        panel.render(buf.area, &mut buf);
+        (0..12).map(|x| buf.get(x, 0).symbol()).collect()
+    }
+
+    #[test]
+    fn trim_drops_leading_spaces() {
+        let panel = || TextPanel::new("Test", "    indented");
+        assert_eq!(first_row(panel()), "  indented  ");
+        assert_eq!(
+            first_row(panel().wrap(Some(Wrap { trim: false }))),
+            "    indented"
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use serde_json::json;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Receives a copy of every line submitted from the input.
+pub trait MessageSink: Send {
+    fn send(&mut self, text: &str) -> io::Result<()>;
+}
+
+/// Writes each message as a JSON object on its own line: `{"ts":<unix millis>,"text":...}`.
+pub struct JsonSink<W: Write + Send> {
+    writer: W,
+}
+
+impl JsonSink<io::Stderr> {
+    pub fn stderr() -> Self {
+        JsonSink {
+            writer: io::stderr(),
+        }
+    }
+}
+
+impl JsonSink<File> {
+    /// Opens a file or named pipe for appending. Opening a pipe blocks until it has a reader.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let writer = OpenOptions::new().append(true).create(true).open(path)?;
+        Ok(JsonSink { writer })
+    }
+}
+
+impl<W: Write + Send> MessageSink for JsonSink<W> {
+    fn send(&mut self, text: &str) -> io::Result<()> {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+        let line = json!({ "ts": ts as u64, "text": text });
+        writeln!(self.writer, "{line}")?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_sink_writes_one_escaped_object_per_line() {
+        let mut sink = JsonSink { writer: Vec::new() };
+        sink.send("plain").unwrap();
+        sink.send("say \"hi\"\n\\").unwrap();
+        let output = String::from_utf8(sink.writer).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for (line, text) in lines.iter().zip(["plain", "say \"hi\"\n\\"]) {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert!(value["ts"].as_u64().is_some_and(|ts| ts > 0));
+            assert_eq!(value["text"], text);
+            assert_eq!(value.as_object().unwrap().len(), 2);
+        }
+        assert!(
+            lines[1].contains(r#""text":"say \"hi\"\n\\""#),
+            "{}",
+            lines[1]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::console::LineLevel;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+/// Colors used to draw the UI. The defaults match the original look: white text on black
+/// panels with yellow highlights for the focused panel.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub focused_border: Color,
+    pub unfocused_border: Color,
+    /// Border of the unfocused panel under the mouse pointer.
+    pub hover_border: Color,
+    pub selection_fg: Color,
+    pub selection_bg: Color,
+    /// Selection colors while the console is not focused, dimmer so it doesn't look active.
+    pub inactive_selection_fg: Color,
+    pub inactive_selection_bg: Color,
+    /// Show selections in reverse video instead of the selection colors, which reads well
+    /// on any terminal palette.
+    pub inverse_selection: bool,
+    /// Text matching the reverse-i-search query in the input.
+    pub search_fg: Color,
+    pub search_bg: Color,
+    pub console_text: Color,
+    /// Console lines the user submitted.
+    pub console_input: Color,
+    /// Console lines reporting a failure.
+    pub console_error: Color,
+    pub input_text: Color,
+    pub panel_fg: Color,
+    pub panel_bg: Color,
+    /// Show focus, selection and errors with bold, reversed and underlined text instead of
+    /// colors, for terminals without color support.
+    pub monochrome: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            focused_border: Color::Yellow,
+            unfocused_border: Color::White,
+            hover_border: Color::LightYellow,
+            selection_fg: Color::Yellow,
+            selection_bg: Color::Blue,
+            inactive_selection_fg: Color::Gray,
+            inactive_selection_bg: Color::DarkGray,
+            inverse_selection: false,
+            search_fg: Color::Black,
+            search_bg: Color::Yellow,
+            console_text: Color::White,
+            console_input: Color::Cyan,
+            console_error: Color::Red,
+            input_text: Color::White,
+            panel_fg: Color::White,
+            panel_bg: Color::Black,
+            monochrome: false,
+        }
+    }
+}
+
+impl Theme {
+    /// Looks up one of the built-in themes by name.
+    pub fn named(name: &str) -> Option<Theme> {
+        match name {
+            "default" | "dark" => Some(Theme::default()),
+            "light" => Some(Theme {
+                focused_border: Color::Blue,
+                unfocused_border: Color::DarkGray,
+                hover_border: Color::LightBlue,
+                selection_fg: Color::White,
+                selection_bg: Color::Blue,
+                inactive_selection_fg: Color::Black,
+                inactive_selection_bg: Color::Gray,
+                inverse_selection: false,
+                search_fg: Color::Black,
+                search_bg: Color::LightYellow,
+                console_text: Color::Black,
+                console_input: Color::Blue,
+                console_error: Color::Red,
+                input_text: Color::Black,
+                panel_fg: Color::Black,
+                panel_bg: Color::White,
+                monochrome: false,
+            }),
+            "mono" | "monochrome" => Some(Theme::monochrome()),
+            _ => None,
+        }
+    }
+
+    /// Every color left to the terminal's defaults.
+    pub fn monochrome() -> Theme {
+        Theme {
+            focused_border: Color::Reset,
+            unfocused_border: Color::Reset,
+            hover_border: Color::Reset,
+            selection_fg: Color::Reset,
+            selection_bg: Color::Reset,
+            inactive_selection_fg: Color::Reset,
+            inactive_selection_bg: Color::Reset,
+            inverse_selection: true,
+            search_fg: Color::Reset,
+            search_bg: Color::Reset,
+            console_text: Color::Reset,
+            console_input: Color::Reset,
+            console_error: Color::Reset,
+            input_text: Color::Reset,
+            panel_fg: Color::Reset,
+            panel_bg: Color::Reset,
+            monochrome: true,
+        }
+    }
+
+    pub fn border(&self, focused: bool) -> Style {
+        if self.monochrome {
+            let style = Style::default();
+            return if focused {
+                style.add_modifier(Modifier::BOLD)
+            } else {
+                style
+            };
+        }
+        if focused {
+            Style::default().fg(self.focused_border)
+        } else {
+            Style::default().fg(self.unfocused_border)
+        }
+    }
+
+    /// Hints that clicking the panel focuses it. Left plain in monochrome, where the only
+    /// border modifier is kept for focus.
+    pub fn hover_border(&self) -> Style {
+        if self.monochrome {
+            return self.border(false);
+        }
+        Style::default().fg(self.hover_border)
+    }
+
+    /// Selected console text, the selected input after submitting, and the highlighted log
+    /// tab and menu item.
+    pub fn selection(&self, focused: bool) -> Style {
+        if self.monochrome || self.inverse_selection {
+            return Style::default().add_modifier(if focused {
+                Modifier::REVERSED
+            } else {
+                Modifier::UNDERLINED
+            });
+        }
+        if focused {
+            Style::default().fg(self.selection_fg).bg(self.selection_bg)
+        } else {
+            Style::default()
+                .fg(self.inactive_selection_fg)
+                .bg(self.inactive_selection_bg)
+        }
+    }
+
+    /// The part of the input matching the reverse-i-search query, set apart from selections.
+    pub fn search_match(&self) -> Style {
+        if self.monochrome || self.inverse_selection {
+            return Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+        }
+        Style::default().fg(self.search_fg).bg(self.search_bg)
+    }
+
+    pub fn console_line(&self, level: LineLevel) -> Style {
+        if self.monochrome {
+            return match level {
+                LineLevel::Error => Style::default().add_modifier(Modifier::BOLD),
+                LineLevel::Info | LineLevel::Input => Style::default(),
+            };
+        }
+        Style::default().fg(match level {
+            LineLevel::Info => self.console_text,
+            LineLevel::Input => self.console_input,
+            LineLevel::Error => self.console_error,
+        })
+    }
+
+    pub fn panel(&self) -> Style {
+        Style::default().fg(self.panel_fg).bg(self.panel_bg)
+    }
+
+    /// Style of the focus indicator in the status bar.
+    pub fn badge(&self) -> Style {
+        if self.monochrome {
+            return Style::default().add_modifier(Modifier::REVERSED);
+        }
+        Style::default().fg(self.panel_bg).bg(self.focused_border)
+    }
+}
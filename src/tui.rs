@@ -1,34 +1,339 @@
 use crate::app::AppEvent;
+use crate::errors::AppError;
 pub use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode as Key},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode as Key, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
 use ratatui::prelude::*;
-use ratatui::Terminal;
-use std::io;
+use ratatui::{Terminal, TerminalOptions, Viewport};
+use std::collections::VecDeque;
+use std::io::{self, Stdout};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
-use tracing::trace;
+use std::thread;
+use std::time::Duration;
+use tracing::{error, trace};
 
-pub fn init_terminal() -> io::Result<Terminal<impl Backend>> {
+/// Whether [`init_terminal`] pushed keyboard enhancement flags that need popping on restore.
+static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
+/// Whether the terminal is on the alternate screen rather than drawing inline.
+static ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+/// Set while [`TerminalGuard::suspend`] has handed the terminal to another program, so that
+/// [`input_thread`] leaves its keys alone.
+static INPUT_PAUSED: AtomicBool = AtomicBool::new(false);
+/// How long [`input_thread`] waits for an event before checking [`INPUT_PAUSED`] again.
+const INPUT_POLL: Duration = Duration::from_millis(50);
+
+/// Owns the terminal and restores it when dropped, so that any early return or error after
+/// [`init_terminal`] still leaves the shell usable.
+pub struct TerminalGuard {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+    restored: bool,
+}
+
+impl TerminalGuard {
+    /// Restores the terminal now, reporting any error instead of ignoring it on drop.
+    pub fn restore(&mut self) -> io::Result<()> {
+        self.restored = true;
+        restore_terminal()
+    }
+
+    /// Switches between the alternate screen and an inline viewport of `inline_height` rows
+    /// below the shell prompt. The next draw repaints everything.
+    pub fn toggle_inline(&mut self, inline_height: u16) -> Result<(), AppError> {
+        let inline_height = if ALTERNATE_SCREEN.load(Ordering::Relaxed) {
+            Some(inline_height)
+        } else {
+            self.terminal.clear().map_err(AppError::TerminalInit)?;
+            None
+        };
+        self.terminal = new_terminal(inline_height)?;
+        Ok(())
+    }
+
+    /// Hands the terminal back to the shell while `child` runs, e.g. an external editor, then
+    /// sets it up again in the same viewport. The next draw repaints everything.
+    pub fn suspend<T>(
+        &mut self,
+        inline_height: u16,
+        child: impl FnOnce() -> T,
+    ) -> Result<T, AppError> {
+        let inline_height = (!ALTERNATE_SCREEN.load(Ordering::Relaxed)).then_some(inline_height);
+        INPUT_PAUSED.store(true, Ordering::Relaxed);
+        let result = restore_terminal()
+            .map(|()| child())
+            .map_err(AppError::TerminalInit);
+        let resumed = enable_raw_mode()
+            .map_err(AppError::TerminalInit)
+            .and_then(|()| new_terminal(inline_height));
+        INPUT_PAUSED.store(false, Ordering::Relaxed);
+        self.terminal = resumed?;
+        enable_input_modes(&mut self.terminal)?;
+        result
+    }
+}
+
+/// A terminal the app can run on: any [`Terminal`], or a [`TerminalGuard`] that can also
+/// switch viewports (F9) and hand the screen to an external editor (Ctrl+X Ctrl+E). A plain
+/// `Terminal`, e.g. over a `TestBackend`, draws the same but turns those two down.
+pub trait AppTerminal {
+    type Backend: Backend;
+
+    fn terminal(&mut self) -> &mut Terminal<Self::Backend>;
+
+    /// Switches between the alternate screen and an inline viewport of `inline_height` rows.
+    fn toggle_inline(&mut self, _inline_height: u16) -> Result<(), AppError> {
+        Err(AppError::Unsupported("switching the viewport"))
+    }
+
+    /// Runs `child` with the terminal handed back to the shell, then takes it back.
+    fn suspend<T>(
+        &mut self,
+        _inline_height: u16,
+        _child: impl FnOnce() -> T,
+    ) -> Result<T, AppError> {
+        Err(AppError::Unsupported("running an external program"))
+    }
+}
+
+impl<B: Backend> AppTerminal for Terminal<B> {
+    type Backend = B;
+
+    fn terminal(&mut self) -> &mut Terminal<B> {
+        self
+    }
+}
+
+impl AppTerminal for TerminalGuard {
+    type Backend = CrosstermBackend<Stdout>;
+
+    fn terminal(&mut self) -> &mut Terminal<Self::Backend> {
+        &mut self.terminal
+    }
+
+    fn toggle_inline(&mut self, inline_height: u16) -> Result<(), AppError> {
+        TerminalGuard::toggle_inline(self, inline_height)
+    }
+
+    fn suspend<T>(&mut self, inline_height: u16, child: impl FnOnce() -> T) -> Result<T, AppError> {
+        TerminalGuard::suspend(self, inline_height, child)
+    }
+}
+
+impl Deref for TerminalGuard {
+    type Target = Terminal<CrosstermBackend<Stdout>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.terminal
+    }
+}
+
+impl DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.terminal
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if !self.restored {
+            let _ = restore_terminal(); // nothing left to report errors to
+        }
+    }
+}
+
+/// Sets up raw mode, the alternate screen, mouse capture and bracketed paste.
+/// See [`init_terminal_with`] to draw inline instead of on the alternate screen.
+///
+/// On terminals that support the Kitty keyboard protocol (kitty, WezTerm, foot, Ghostty and
+/// recent Alacritty) this also asks for disambiguated escape codes. That makes chords such as
+/// `Shift+Enter`, `Ctrl+Enter`, `Ctrl+I` (distinct from `Tab`) and `Ctrl+Shift+<letter>`
+/// reach the app with their modifiers intact. Elsewhere the flags are skipped and those chords
+/// arrive as their unmodified keys.
+pub fn init_terminal() -> Result<TerminalGuard, AppError> {
+    init_terminal_with(None)
+}
+
+/// Like [`init_terminal`], but with `inline_height` set the app draws in that many rows below
+/// the shell prompt and leaves the scrollback alone.
+pub fn init_terminal_with(inline_height: Option<u16>) -> Result<TerminalGuard, AppError> {
     trace!(target:"crossterm", "Initializing terminal");
-    enable_raw_mode()?;
-    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(io::stdout());
-    Terminal::new(backend)
+    enable_raw_mode().map_err(AppError::TerminalInit)?;
+    let terminal = new_terminal(inline_height).inspect_err(|_| {
+        let _ = restore_terminal(); // the original error is the one worth reporting
+    })?;
+    let mut guard = TerminalGuard {
+        terminal,
+        restored: false,
+    };
+    enable_input_modes(&mut guard)?;
+    Ok(guard)
+}
+
+/// Turns on mouse capture, bracketed paste and, where supported, keyboard enhancement.
+fn enable_input_modes(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<(), AppError> {
+    execute!(
+        terminal.backend_mut(),
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )
+    .map_err(AppError::TerminalInit)?;
+    // the query needs raw mode, and a failed query just means no support
+    if supports_keyboard_enhancement().unwrap_or(false) {
+        execute!(
+            terminal.backend_mut(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        )
+        .map_err(AppError::TerminalInit)?;
+        KEYBOARD_ENHANCED.store(true, Ordering::Relaxed);
+    }
+    trace!(target:"crossterm", "Keyboard enhancement: {}", KEYBOARD_ENHANCED.load(Ordering::Relaxed));
+    Ok(())
+}
+
+/// Enters or leaves the alternate screen to match `inline_height` and builds a terminal with
+/// the matching viewport. Needs raw mode, as an inline viewport asks for the cursor position.
+fn new_terminal(
+    inline_height: Option<u16>,
+) -> Result<Terminal<CrosstermBackend<Stdout>>, AppError> {
+    let viewport = match inline_height {
+        Some(height) => {
+            if ALTERNATE_SCREEN.swap(false, Ordering::Relaxed) {
+                execute!(io::stdout(), LeaveAlternateScreen).map_err(AppError::TerminalInit)?;
+            }
+            Viewport::Inline(height)
+        }
+        None => {
+            if !ALTERNATE_SCREEN.swap(true, Ordering::Relaxed) {
+                execute!(io::stdout(), EnterAlternateScreen).map_err(AppError::TerminalInit)?;
+            }
+            Viewport::Fullscreen
+        }
+    };
+    Terminal::with_options(
+        CrosstermBackend::new(io::stdout()),
+        TerminalOptions { viewport },
+    )
+    .map_err(AppError::TerminalInit)
 }
 
 pub fn restore_terminal() -> io::Result<()> {
     trace!(target:"crossterm", "Restoring terminal");
+    if KEYBOARD_ENHANCED.swap(false, Ordering::Relaxed) {
+        execute!(io::stdout(), PopKeyboardEnhancementFlags)?;
+    }
     disable_raw_mode()?;
-    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)
+    if ALTERNATE_SCREEN.swap(false, Ordering::Relaxed) {
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+    }
+    execute!(io::stdout(), DisableMouseCapture, DisableBracketedPaste)
+}
+
+/// Turns mouse capture on or off. While it is off the terminal handles mouse selection itself.
+pub fn set_mouse_capture(enabled: bool) -> io::Result<()> {
+    trace!(target:"crossterm", "Setting mouse capture to {}", enabled);
+    if enabled {
+        execute!(io::stdout(), EnableMouseCapture)
+    } else {
+        execute!(io::stdout(), DisableMouseCapture)
+    }
+}
+
+/// Where the app's input comes from. [`App::start`](crate::App::start) reads it on a thread of
+/// its own and feeds the events into the same channel as the background tasks.
+pub trait EventSource: Send + 'static {
+    /// Blocks until the next event. `None` ends the source for good.
+    fn next_event(&mut self) -> Option<AppEvent>;
 }
 
-pub fn input_thread(tx_event: mpsc::Sender<AppEvent>) -> anyhow::Result<()> {
+/// Keys, mouse, paste and resize events from the terminal.
+#[derive(Debug, Default)]
+pub struct CrosstermEvents;
+
+impl EventSource for CrosstermEvents {
+    fn next_event(&mut self) -> Option<AppEvent> {
+        loop {
+            if INPUT_PAUSED.load(Ordering::Relaxed) {
+                thread::sleep(INPUT_POLL);
+                continue;
+            }
+            // poll rather than block so that a suspend takes effect before the next key
+            let event =
+                event::poll(INPUT_POLL).and_then(|ready| ready.then(event::read).transpose());
+            match event {
+                Ok(Some(event)) => {
+                    trace!(target:"crossterm", "Stdin event received {:?}", event);
+                    return Some(AppEvent::UiEvent(event));
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    error!(target:"crossterm", "Reading terminal input failed: {}", err);
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// A fixed list of events, e.g. keys, resizes and ticks for a test, that ends once they have
+/// all been read.
+#[derive(Debug, Default)]
+pub struct ScriptedEvents {
+    events: VecDeque<AppEvent>,
+}
+
+impl ScriptedEvents {
+    pub fn new(events: impl IntoIterator<Item = AppEvent>) -> ScriptedEvents {
+        ScriptedEvents {
+            events: events.into_iter().collect(),
+        }
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn next_event(&mut self) -> Option<AppEvent> {
+        self.events.pop_front()
+    }
+}
+
+/// Forwards events from `source` to the app until either the source or the app goes away.
+pub fn input_thread(
+    mut source: impl EventSource,
+    tx_event: mpsc::Sender<AppEvent>,
+) -> anyhow::Result<()> {
     trace!(target:"crossterm", "Starting input thread");
-    while let Ok(event) = event::read() {
-        trace!(target:"crossterm", "Stdin event received {:?}", event);
-        tx_event.send(AppEvent::UiEvent(event))?;
+    while let Some(event) = source.next_event() {
+        tx_event.send(event)?;
+    }
+    Ok(())
+}
+
+/// Turns SIGTERM, SIGINT and SIGHUP into [`AppEvent::Quit`] so that the normal shutdown runs
+/// and restores the terminal. A second signal, or one arriving after the UI has gone away,
+/// restores the terminal and exits immediately.
+#[cfg(unix)]
+pub fn signal_thread(tx_event: mpsc::Sender<AppEvent>) -> anyhow::Result<()> {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP])?;
+    let mut quitting = false;
+    for signal in signals.forever() {
+        trace!(target:"crossterm", "Received signal {}", signal);
+        if quitting || tx_event.send(AppEvent::Quit).is_err() {
+            let _ = restore_terminal(); // exiting anyway
+            std::process::exit(128 + signal);
+        }
+        quitting = true;
     }
     Ok(())
 }